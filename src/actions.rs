@@ -1,37 +1,36 @@
 use crate::entry::Entry;
 
 pub fn get_builtin_actions() -> Vec<Entry> {
-    let mut actions: Vec<Entry> = Vec::new();
-
-    actions.push(Entry::builder()
-        .id("yde-launcher:action:logout")
-        .title("Logout")
-        .description(Some("Log out of the currently active session"))
-        .icon(Some("system-log-out"))
-        .launch_command(Some("sh -c 'loginctl terminate-session $XDG_SESSION_ID'"))
-        .is_terminal(false)
-        .user_confirm(true)
-        .build());
-
-    actions.push(Entry::builder()
-        .id("yde-launcher:action:shutdown")
-        .title("Shutdown")
-        .description(Some("Shut down the system"))
-        .icon(Some("system-shutdown"))
-        .launch_command(Some("shutdown now"))
-        .is_terminal(false)
-        .user_confirm(true)
-        .build());
-
-    actions.push(Entry::builder()
-        .id("yde-launcher:action:restart")
-        .title("Restart")
-        .description(Some("Restart the system"))
-        .icon(Some("system-reboot"))
-        .launch_command(Some("shutdown -r now"))
-        .is_terminal(false)
-        .user_confirm(true)
-        .build());
-
-    actions
-}
\ No newline at end of file
+    vec![
+        Entry::builder()
+            .id("yde-launcher:action:logout")
+            .title("Logout")
+            .description(Some("Log out of the currently active session"))
+            .icon(Some("system-log-out"))
+            .launch_command(Some("sh -c \"loginctl terminate-session \\$XDG_SESSION_ID\""))
+            .categories(vec!["System".to_string()])
+            .is_terminal(false)
+            .user_confirm(true)
+            .build(),
+        Entry::builder()
+            .id("yde-launcher:action:shutdown")
+            .title("Shutdown")
+            .description(Some("Shut down the system"))
+            .icon(Some("system-shutdown"))
+            .launch_command(Some("shutdown now"))
+            .categories(vec!["System".to_string()])
+            .is_terminal(false)
+            .user_confirm(true)
+            .build(),
+        Entry::builder()
+            .id("yde-launcher:action:restart")
+            .title("Restart")
+            .description(Some("Restart the system"))
+            .icon(Some("system-reboot"))
+            .launch_command(Some("shutdown -r now"))
+            .categories(vec!["System".to_string()])
+            .is_terminal(false)
+            .user_confirm(true)
+            .build(),
+    ]
+}
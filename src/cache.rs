@@ -0,0 +1,126 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::data::{self, AppsDirItem};
+use crate::entry::Entry;
+use crate::icons;
+use crate::locale;
+use crate::utils;
+
+/// Bumped whenever the cached data changes shape or meaning.
+const CACHE_VERSION: u32 = 2;
+
+/// Everything the cached entries were derived from. The cache is only valid while it matches.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheKey {
+    version: u32,
+    context: Vec<String>,
+    stamps: BTreeMap<PathBuf, u128>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cache {
+    pub key: CacheKey,
+    pub entries: Vec<Entry>,
+    pub icon_map: HashMap<String, PathBuf>,
+}
+
+pub fn get_cache_home() -> Option<PathBuf> {
+    utils::xdg_home("XDG_CACHE_HOME", ".cache")
+}
+
+pub fn get_cache_path() -> Option<PathBuf> {
+    get_cache_home().map(|dir| dir.join("yde-launcher").join("entries.json"))
+}
+
+fn mtime(path: &Path) -> Option<u128> {
+    let modified = path.metadata().ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_nanos())
+}
+
+/// Computes the key for the current environment: the mtimes of every `applications` directory
+/// and desktop file, the top-level icon theme directories, the `$PATH` directories (programs
+/// decide `TryExec` and missing executables), and the settings that change how desktop files
+/// are evaluated.
+pub fn current_key(config: &Config) -> CacheKey {
+    let data_dirs = data::get_data_dirs();
+    let mut stamps = BTreeMap::new();
+    for dir in &data_dirs {
+        data::walk_apps_dir(&dir.join("applications"), &mut |item| {
+            let (AppsDirItem::Dir(path) | AppsDirItem::DesktopFile(path)) = item;
+            if let Some(path_mtime) = mtime(path) {
+                stamps.insert(path.to_path_buf(), path_mtime);
+            }
+        });
+    }
+    for dir in icons::get_icon_dirs(&data_dirs) {
+        if let Some(dir_mtime) = mtime(&dir) {
+            stamps.insert(dir.clone(), dir_mtime);
+        }
+        if let Ok(themes) = std::fs::read_dir(&dir) {
+            for theme in themes.flatten().map(|entry| entry.path()) {
+                if let Some(theme_mtime) = mtime(&theme) {
+                    stamps.insert(theme, theme_mtime);
+                }
+            }
+        }
+    }
+    // Installing or removing a program changes the mtime of its directory.
+    if let Some(path_var) = std::env::var_os("PATH") {
+        for dir in std::env::split_paths(&path_var) {
+            if let Some(dir_mtime) = mtime(&dir) {
+                stamps.insert(dir, dir_mtime);
+            }
+        }
+    }
+
+    let mut context = vec![
+        format!("desktops={}", config.current_desktops().join(":")),
+        format!("show-filtered={}", config.show_filtered_entries),
+        format!("icon-theme={}", linicon_theme::get_icon_theme().unwrap_or_default()),
+        format!("path={}", std::env::var("PATH").unwrap_or_default()),
+    ];
+    context.extend(locale::current_locale_candidates().iter().map(|locale| format!("locale={locale}")));
+
+    CacheKey {
+        version: CACHE_VERSION,
+        context,
+        stamps,
+    }
+}
+
+/// Loads the cache without validating it, so the window can be painted right away.
+pub fn load() -> Option<Cache> {
+    let path = get_cache_path()?;
+    let content = std::fs::read(&path).ok()?;
+    match serde_json::from_slice::<Cache>(&content) {
+        Ok(cache) if cache.key.version == CACHE_VERSION => Some(cache),
+        Ok(_) => None,
+        Err(err) => {
+            log::warn!("Ignoring unreadable cache {:?}: {}", path, err);
+            None
+        }
+    }
+}
+
+pub fn store(key: CacheKey, entries: &[Entry], icon_map: &HashMap<String, PathBuf>) {
+    let Some(path) = get_cache_path() else {
+        return;
+    };
+    let cache = Cache {
+        key,
+        entries: entries.to_vec(),
+        icon_map: icon_map.clone(),
+    };
+    // A concurrent start must never read a partial cache.
+    let result = serde_json::to_vec(&cache)
+        .map_err(anyhow::Error::from)
+        .and_then(|content| Ok(utils::write_atomic(&path, &content)?));
+    if let Err(err) = result {
+        log::warn!("Failed to write cache {:?}: {}", path, err);
+    }
+}
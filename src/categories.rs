@@ -0,0 +1,49 @@
+use crate::entry::Entry;
+
+/// The registered main categories, in the order their sections are listed.
+/// `Audio` and `Video` are folded into `AudioVideo`, which they require anyway.
+pub const MAIN_CATEGORIES: &[(&str, &str)] = &[
+    ("AudioVideo", "Multimedia"),
+    ("Development", "Development"),
+    ("Education", "Education"),
+    ("Game", "Games"),
+    ("Graphics", "Graphics"),
+    ("Network", "Internet"),
+    ("Office", "Office"),
+    ("Science", "Science"),
+    ("Settings", "Settings"),
+    ("System", "System"),
+    ("Utility", "Accessories"),
+];
+
+pub const OTHER_CATEGORY: &str = "Other";
+
+/// Returns the main category an entry is listed under: the first main category in its
+/// `Categories` key, or [`OTHER_CATEGORY`] when it has none.
+pub fn main_category(entry: &Entry) -> &'static str {
+    entry.categories().iter()
+        .find_map(|category| {
+            let category = match category.as_str() {
+                "Audio" | "Video" => "AudioVideo",
+                category => category,
+            };
+            MAIN_CATEGORIES.iter()
+                .find(|(name, _)| *name == category)
+                .map(|(name, _)| *name)
+        })
+        .unwrap_or(OTHER_CATEGORY)
+}
+
+pub fn display_name(category: &str) -> &str {
+    MAIN_CATEGORIES.iter()
+        .find(|(name, _)| *name == category)
+        .map(|(_, display_name)| *display_name)
+        .unwrap_or(category)
+}
+
+/// Sort position of a section; `Other` is always last.
+pub fn sort_index(category: &str) -> usize {
+    MAIN_CATEGORIES.iter()
+        .position(|(name, _)| *name == category)
+        .unwrap_or(MAIN_CATEGORIES.len())
+}
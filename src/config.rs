@@ -0,0 +1,142 @@
+use std::path::PathBuf;
+
+use crate::keyfile::KeyFile;
+use crate::matcher::MatcherKind;
+use crate::utils;
+
+const CONFIG_GROUP: &str = "Launcher";
+
+/// Multipliers applied to the match score of each searchable field.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchWeights {
+    pub title: f64,
+    pub generic_name: f64,
+    pub keywords: f64,
+    pub categories: f64,
+    pub executable: f64,
+    /// Multiplier for the launch history bonus; 0 ranks by match quality alone.
+    pub frecency: f64,
+    /// Multiplier for the bonus of entries previously chosen for the same query.
+    pub association: f64,
+}
+
+impl Default for SearchWeights {
+    fn default() -> Self {
+        Self {
+            title: 1.0,
+            generic_name: 0.8,
+            keywords: 0.6,
+            categories: 0.4,
+            executable: 0.5,
+            frecency: 1.0,
+            association: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    /// Overrides `XDG_CURRENT_DESKTOP` when evaluating `OnlyShowIn` and `NotShowIn`.
+    pub current_desktop: Option<Vec<String>>,
+    /// Show entries excluded by `OnlyShowIn`/`NotShowIn` greyed out instead of hiding them.
+    pub show_filtered_entries: bool,
+    pub search_weights: SearchWeights,
+    /// The matching algorithm used when the query does not select one with a sigil.
+    pub matcher: MatcherKind,
+    /// Match Cyrillic and Greek names by their Latin transliteration.
+    pub transliterate: bool,
+    /// Extra `character=replacement` tables, e.g. for CJK romanization.
+    pub transliteration_tables: Vec<PathBuf>,
+}
+
+pub fn get_config_home() -> Option<PathBuf> {
+    utils::xdg_home("XDG_CONFIG_HOME", ".config")
+}
+
+pub fn get_config_path() -> Option<PathBuf> {
+    get_config_home().map(|dir| dir.join("yde-launcher").join("config"))
+}
+
+fn get_string<'a>(entry: &'a KeyFile, key: &str) -> Option<&'a str> {
+    entry.get(CONFIG_GROUP, key)
+}
+
+fn get_float(entry: &KeyFile, key: &str) -> Option<f64> {
+    get_string(entry, key).and_then(|value| value.parse().ok())
+}
+
+fn get_bool(entry: &KeyFile, key: &str) -> Option<bool> {
+    get_string(entry, key).map(|value| value == "true")
+}
+
+impl Config {
+    /// Loads the `[Launcher]` group of `$XDG_CONFIG_HOME/yde-launcher/config`, falling back to
+    /// the defaults when the file is missing or cannot be parsed.
+    pub fn load() -> Self {
+        let Some(path) = get_config_path().filter(|path| path.exists()) else {
+            return Config::default();
+        };
+        let entry = match KeyFile::parse_file(&path) {
+            Ok(entry) => entry,
+            Err(err) => {
+                log::error!("Failed to parse config file {:?}: {}", path, err);
+                return Config::default();
+            }
+        };
+
+        let mut config = Config {
+            current_desktop: get_string(&entry, "CurrentDesktop")
+                .map(|value| value.split(':').filter(|d| !d.is_empty()).map(str::to_string).collect()),
+            ..Default::default()
+        };
+        if let Some(value) = get_bool(&entry, "ShowFilteredEntries") {
+            config.show_filtered_entries = value;
+        }
+
+        if let Some(value) = get_bool(&entry, "Transliterate") {
+            config.transliterate = value;
+        }
+        if let Some(value) = get_string(&entry, "TransliterationTables") {
+            // Relative paths are resolved against the directory of the config file.
+            let config_dir = path.parent().unwrap_or(&path);
+            config.transliteration_tables = value.split(';')
+                .filter(|table| !table.is_empty())
+                .map(|table| config_dir.join(table))
+                .collect();
+        }
+        if let Some(value) = get_string(&entry, "Matcher") {
+            match MatcherKind::from_name(value) {
+                Some(kind) => config.matcher = kind,
+                None => log::warn!("Unknown matcher {:?} in {:?}", value, path),
+            }
+        }
+
+        let weights = &mut config.search_weights;
+        for (key, weight) in [
+            ("TitleWeight", &mut weights.title),
+            ("GenericNameWeight", &mut weights.generic_name),
+            ("KeywordsWeight", &mut weights.keywords),
+            ("CategoriesWeight", &mut weights.categories),
+            ("ExecutableWeight", &mut weights.executable),
+            ("FrecencyWeight", &mut weights.frecency),
+            ("AssociationWeight", &mut weights.association),
+        ] {
+            if let Some(value) = get_float(&entry, key) {
+                *weight = value;
+            }
+        }
+        config
+    }
+
+    /// Returns the desktop names to match against, from the config or `XDG_CURRENT_DESKTOP`.
+    pub fn current_desktops(&self) -> Vec<String> {
+        if let Some(desktops) = &self.current_desktop {
+            return desktops.clone();
+        }
+        std::env::var("XDG_CURRENT_DESKTOP").unwrap_or_default()
+            .split(':')
+            .filter(|d| !d.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+}
@@ -1,133 +1,530 @@
-use std::{path::PathBuf};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use rayon::iter::IntoParallelRefIterator;
 use rayon::iter::ParallelIterator;
+use serde::Serialize;
 
-use crate::entry::Entry;
+use crate::config::Config;
+use crate::entry::{Entry, EntryKind};
+use crate::exec;
+use crate::keyfile::{self, KeyFile};
+use crate::locale;
+use crate::utils;
 
+fn get_env_dirs(var: &str) -> Option<Vec<PathBuf>> {
+    let value = std::env::var(var).ok()?;
+    // Relative paths are invalid in the XDG variables and must be ignored.
+    let dirs: Vec<PathBuf> = value.split(':')
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .collect();
+
+    if dirs.is_empty() { None } else { Some(dirs) }
+}
+
+pub fn get_data_home() -> Option<PathBuf> {
+    utils::xdg_home("XDG_DATA_HOME", ".local/share")
+}
+
+/// Returns the base data directories in order of preference, starting with `XDG_DATA_HOME`.
 pub fn get_data_dirs() -> Vec<PathBuf> {
-    let dirs_string = std::env::var("XDG_DATA_DIRS").unwrap_or_default();
-    
-    dirs_string.split(':').map(PathBuf::from).collect()
+    let system_dirs = get_env_dirs("XDG_DATA_DIRS")
+        .unwrap_or_else(|| vec![PathBuf::from("/usr/local/share"), PathBuf::from("/usr/share")]);
+
+    let mut seen = HashSet::new();
+    get_data_home().into_iter()
+        .chain(system_dirs)
+        .filter(|dir| seen.insert(dir.clone()))
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DesktopFile {
+    pub id: String,
+    pub path: PathBuf,
+    pub apps_dir: PathBuf,
+}
+
+fn desktop_file_id(apps_dir: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(apps_dir).ok()?;
+    let parts: Vec<&str> = relative.iter().map(|part| part.to_str()).collect::<Option<_>>()?;
+    Some(parts.join("-"))
+}
+
+/// A directory or desktop file found by `walk_apps_dir`.
+pub enum AppsDirItem<'a> {
+    Dir(&'a Path),
+    DesktopFile(&'a Path),
+}
+
+/// Visits an `applications` directory, its subdirectories and the desktop files in them, in
+/// sorted order.
+pub fn walk_apps_dir(apps_dir: &Path, visit: &mut impl FnMut(AppsDirItem)) {
+    walk_dir(apps_dir, &mut HashSet::new(), visit);
+}
+
+fn walk_dir(dir: &Path, visited: &mut HashSet<PathBuf>, visit: &mut impl FnMut(AppsDirItem)) {
+    // Symlinked directories may point back up the tree.
+    let Ok(canonical) = dir.canonicalize() else {
+        return;
+    };
+    if !visited.insert(canonical) {
+        return;
+    }
+    visit(AppsDirItem::Dir(dir));
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+
+    let mut paths: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+    paths.sort();
+    for path in paths {
+        if path.is_dir() {
+            walk_dir(&path, visited, visit);
+        } else if path.extension().and_then(|s| s.to_str()) == Some("desktop") {
+            visit(AppsDirItem::DesktopFile(&path));
+        }
+    }
 }
 
-pub fn get_desktop_entry_paths() -> Vec<PathBuf> {
-    let dirs = get_data_dirs();
+/// Returns every desktop file in the data directories, most preferred directory first.
+pub fn scan_desktop_files() -> Vec<DesktopFile> {
+    scan_data_dirs(&get_data_dirs())
+}
 
-    let desktop_entries: Vec<PathBuf> = dirs.par_iter().map(|dir|{
+fn scan_data_dirs(dirs: &[PathBuf]) -> Vec<DesktopFile> {
+    dirs.par_iter().map(|dir|{
         let apps_dir = dir.join("applications");
         let mut desktop_entries = Vec::new();
-        if apps_dir.exists() && apps_dir.is_dir() {
-            if let Ok(entries) = std::fs::read_dir(apps_dir) {
-                for entry in entries.flatten() {
-                    let path = entry.path();
-                    if path.extension().and_then(|s| s.to_str()) == Some("desktop") {
-                        desktop_entries.push(path);
-                    }
-                }
+        walk_apps_dir(&apps_dir, &mut |item| {
+            if let AppsDirItem::DesktopFile(path) = item
+                && let Some(id) = desktop_file_id(&apps_dir, path)
+            {
+                desktop_entries.push(DesktopFile { id, path: path.to_path_buf(), apps_dir: apps_dir.clone() });
             }
-        }
+        });
         desktop_entries
-    }).flatten().collect();
+    }).flatten().collect()
+}
 
-    desktop_entries
+/// Returns the desktop files that are visible after shadowing: when several data directories
+/// contain the same desktop file ID, only the one from the most preferred directory is kept.
+pub fn get_desktop_entry_paths() -> Vec<DesktopFile> {
+    shadow_duplicates(scan_desktop_files())
 }
 
-pub fn get_desktop_entries() -> Vec<Entry> {
-    let paths = get_desktop_entry_paths();
+fn shadow_duplicates(desktop_entries: Vec<DesktopFile>) -> Vec<DesktopFile> {
+    let mut seen_ids = HashSet::new();
+    desktop_entries.into_iter()
+        .filter(|file| seen_ids.insert(file.id.clone()))
+        .collect()
+}
 
-    let entries: Vec<Entry> = paths.par_iter().filter_map(|path|{
-        match parse_desktop_entry(path) {
-            Ok(entry) => {
-                let no_display = entry.entry.get("Desktop Entry", "NoDisplay")
-                    .map(|e| e.iter().filter(|t| t.as_str() == "true").collect::<Vec<_>>().len() > 0).unwrap_or(false);
+/// Why a desktop file does not produce any entries.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "reason", rename_all = "kebab-case")]
+pub enum Exclusion {
+    ParseError { message: String, line: Option<usize> },
+    NoDisplay,
+    Hidden,
+    Directory,
+    UnsupportedType { message: String },
+    NotShownIn { desktops: Vec<String> },
+    TryExec { program: String },
+    Duplicate { shadowed_by: PathBuf },
+}
 
-                if no_display {
-                    return None;
-                }
+impl std::fmt::Display for Exclusion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Exclusion::ParseError { message, line: Some(line) } => write!(f, "parse error on line {line}: {message}"),
+            Exclusion::ParseError { message, line: None } => write!(f, "parse error: {message}"),
+            Exclusion::NoDisplay => write!(f, "NoDisplay=true"),
+            Exclusion::Hidden => write!(f, "Hidden=true"),
+            Exclusion::Directory => write!(f, "Type=Directory describes a menu folder"),
+            Exclusion::UnsupportedType { message } => write!(f, "{message}"),
+            Exclusion::NotShownIn { desktops } => write!(f, "not shown in current desktop {:?} (OnlyShowIn/NotShowIn)", desktops.join(":")),
+            Exclusion::TryExec { program } => write!(f, "TryExec program {program:?} not found"),
+            Exclusion::Duplicate { shadowed_by } => write!(f, "shadowed by {}", shadowed_by.display()),
+        }
+    }
+}
 
-                let hidden = entry.entry.get("Desktop Entry", "Hidden")
-                    .map(|e| e.iter().filter(|t| t.as_str() == "true").collect::<Vec<_>>().len() > 0).unwrap_or(false);
+/// Parses a desktop file and applies the visibility rules, returning the entries it provides
+/// (the application itself and its desktop actions) or the reason it is excluded.
+pub fn evaluate_desktop_file(file: &DesktopFile, config: &Config, desktops: &[String]) -> Result<Vec<Entry>, Exclusion> {
+    let entry = parse_desktop_entry(file).map_err(|err| Exclusion::ParseError {
+        message: err.to_string(),
+        line: err.downcast_ref::<keyfile::ParseError>().map(|err| err.line),
+    })?;
 
-                if hidden {
-                    return None;
-                }
+    if entry.get_bool("Desktop Entry", "NoDisplay") {
+        return Err(Exclusion::NoDisplay);
+    }
+
+    if entry.get_bool("Desktop Entry", "Hidden") {
+        return Err(Exclusion::Hidden);
+    }
+
+    let kind = match entry.kind() {
+        Ok(Some(kind)) => kind,
+        // Directory entries only describe menu folders and are never listed.
+        Ok(None) => return Err(Exclusion::Directory),
+        Err(err) => return Err(Exclusion::UnsupportedType { message: err.to_string() }),
+    };
+
+    if kind == EntryKind::Application
+        && let Some(try_exec) = entry.get_string("Desktop Entry", "TryExec")
+        && utils::find_executable(try_exec).is_none()
+    {
+        return Err(Exclusion::TryExec { program: try_exec.to_string() });
+    }
+
+    let shown_in_desktop = entry.is_shown_in(desktops);
+    if !shown_in_desktop && !config.show_filtered_entries {
+        return Err(Exclusion::NotShownIn { desktops: desktops.to_vec() });
+    }
 
-                Some(entry.into())
-            },
+    let action_entries = match kind {
+        EntryKind::Application => entry.action_entries(),
+        EntryKind::Link { .. } => vec![],
+    };
+    let entry: Entry = entry.into();
+    Ok(std::iter::once(entry)
+        .chain(action_entries)
+        .map(|entry| entry.into_builder().is_desktop_filtered(!shown_in_desktop).build())
+        .collect())
+}
+
+pub fn get_desktop_entries(config: &Config) -> Vec<Entry> {
+    let paths = get_desktop_entry_paths();
+    let desktops = config.current_desktops();
+
+    let entries: Vec<Entry> = paths.par_iter().filter_map(|file|{
+        match evaluate_desktop_file(file, config, &desktops) {
+            Ok(entries) => Some(entries),
+            Err(exclusion @ (Exclusion::ParseError { .. } | Exclusion::UnsupportedType { .. })) => {
+                log::warn!("Skipping {:?}: {}", file.path, exclusion);
+                None
+            }
             Err(_) => {
                 None
             }
         }
-    }).collect();
+    }).flatten().collect();
 
     entries
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DesktopEntry {
+    id: String,
     path: PathBuf,
-    entry: freedesktop_entry_parser::Entry,
-}
-
-impl Into<Entry> for DesktopEntry {
-    fn into(self) -> Entry {
-        let title = {
-            self.entry.get("Desktop Entry", "Name")
-                .map(|e|e.first())
-                .flatten()
-                .map(|e|e.as_str())
-                .unwrap_or("Unnamed")
-        };
-
-        let generic_name = {
-            self.entry.get("Desktop Entry", "GenericName")
-                .map(|e|e.first())
-                .flatten()
-                .map(|e|e.as_str())
-        };
-
-        let description = {
-            self.entry.get("Desktop Entry", "Comment")
-                .map(|e|e.first())
-                .flatten()
-                .map(|e|e.as_str())
-        };
-
-        let is_terminal = self.entry.get("Desktop Entry", "Terminal")
-            .map(|e| e.iter().filter(|t| t.as_str() == "true").collect::<Vec<_>>().len() > 0)
-            .unwrap_or(false);
-
-        let id = self.path.to_str().unwrap_or_default().to_string();
-
-        let icon = {
-            self.entry.get("Desktop Entry", "Icon")
-                .map(|e| e.first())
-                .flatten()
-                .map(|e| e.to_string())
-        };
-
-        let launch_command = {
-            self.entry.get("Desktop Entry", "Exec")
-                .map(|e| e.first())
-                .flatten()
-                .map(|e| e.to_string())
-        };
+    entry: KeyFile,
+}
+
+impl DesktopEntry {
+    fn get_string(&self, group: &str, key: &str) -> Option<&str> {
+        self.entry.get(group, key)
+    }
+
+    fn get_bool(&self, group: &str, key: &str) -> bool {
+        self.entry.get(group, key) == Some("true")
+    }
+
+    fn get_list(&self, group: &str, key: &str) -> Vec<String> {
+        self.entry.get_list(group, key)
+    }
+
+    fn get_localized_list(&self, group: &str, key: &str) -> Vec<String> {
+        let mut values = locale::current_locale_candidates().iter()
+            .map(|locale| self.get_list(group, &format!("{key}[{locale}]")))
+            .find(|values| !values.is_empty())
+            .unwrap_or_default();
+        for value in self.get_list(group, key) {
+            if !values.contains(&value) {
+                values.push(value);
+            }
+        }
+        values
+    }
+
+    /// Evaluates `OnlyShowIn` and `NotShowIn`. The first desktop of the current list that
+    /// appears in either key decides; otherwise the entry is shown unless `OnlyShowIn` is set.
+    pub fn is_shown_in(&self, desktops: &[String]) -> bool {
+        let only_show_in = self.get_list("Desktop Entry", "OnlyShowIn");
+        let not_show_in = self.get_list("Desktop Entry", "NotShowIn");
+
+        for desktop in desktops {
+            if only_show_in.contains(desktop) {
+                return true;
+            }
+            if not_show_in.contains(desktop) {
+                return false;
+            }
+        }
+        only_show_in.is_empty()
+    }
+
+    fn get_localized(&self, group: &str, key: &str) -> Option<&str> {
+        locale::current_locale_candidates().iter()
+            .find_map(|locale| self.get_string(group, &format!("{key}[{locale}]")))
+            .or_else(|| self.get_string(group, key))
+    }
+
+    /// Determines the entry kind from the `Type` key. Returns `Ok(None)` for `Type=Directory`.
+    pub fn kind(&self) -> anyhow::Result<Option<EntryKind>> {
+        match self.get_string("Desktop Entry", "Type") {
+            Some("Application") => Ok(Some(EntryKind::Application)),
+            Some("Link") => {
+                let url = self.get_string("Desktop Entry", "URL")
+                    .ok_or_else(|| anyhow::anyhow!("Type=Link entry has no URL key"))?;
+                Ok(Some(EntryKind::Link { url: url.to_string() }))
+            }
+            Some("Directory") => Ok(None),
+            Some(other) => anyhow::bail!("Unsupported Type={other}"),
+            None => anyhow::bail!("Missing required Type key"),
+        }
+    }
+
+    /// Builds a launchable entry for every `[Desktop Action <name>]` group listed in `Actions`.
+    pub fn action_entries(&self) -> Vec<Entry> {
+        let parent_title = self.get_localized("Desktop Entry", "Name").unwrap_or("Unnamed");
+        let parent_untranslated_title = self.get_string("Desktop Entry", "Name").unwrap_or("Unnamed");
+        let parent_icon = self.get_string("Desktop Entry", "Icon");
+        let is_terminal = self.get_bool("Desktop Entry", "Terminal");
+        let is_dbus_activatable = self.get_bool("Desktop Entry", "DBusActivatable");
+        let startup_notify = self.get_bool("Desktop Entry", "StartupNotify");
+        let startup_wm_class = self.get_string("Desktop Entry", "StartupWMClass");
+        let working_dir = self.get_string("Desktop Entry", "Path").filter(|path| !path.is_empty());
+
+        self.get_list("Desktop Entry", "Actions").into_iter().filter_map(|action| {
+            let group = format!("Desktop Action {action}");
+            let name = self.get_localized(&group, "Name")?;
+            let title = format!("{parent_title}: {name}");
+            let untranslated_title = self.get_string(&group, "Name")
+                .map(|name| format!("{parent_untranslated_title}: {name}"))
+                .filter(|untranslated_title| *untranslated_title != title);
+            let icon = self.get_string(&group, "Icon").or(parent_icon);
+            // Exec has its own quoting rules, applied together with the escapes by the tokenizer.
+            let launch_command = self.entry.get_raw(&group, "Exec");
+
+            Some(Entry::builder()
+                .id(&format!("{}:{}", self.id, action))
+                .parent_id(Some(self.id.as_str()))
+                .title(title)
+                .untranslated_title(untranslated_title)
+                .executable(executable_name(launch_command))
+                .icon(icon)
+                .launch_command(launch_command)
+                .is_terminal(is_terminal)
+                .is_dbus_activatable(is_dbus_activatable)
+                .startup_notify(startup_notify)
+                .startup_wm_class(startup_wm_class)
+                .working_dir(working_dir)
+                .is_missing_executable(is_missing_executable(launch_command))
+                .user_confirm(false)
+                .source_path(Some(self.path.clone()))
+                .build())
+        }).collect()
+    }
+}
+
+/// Returns the file name of the program an Exec value runs, looking through `env` wrappers.
+fn executable_name(launch_command: Option<&str>) -> Option<String> {
+    let args = exec::parse_exec(launch_command?).ok()?;
+    let mut args = args.iter().map(|arg| arg.as_str());
+    let mut program = args.next()?;
+    if Path::new(program).file_name().is_some_and(|name| name == "env") {
+        program = args.find(|arg| !arg.starts_with('-') && !arg.contains('='))?;
+    }
+    Path::new(program).file_name().map(|name| name.to_string_lossy().into_owned())
+}
+
+fn is_missing_executable(launch_command: Option<&str>) -> bool {
+    launch_command
+        .and_then(|command| exec::parse_exec(command).ok())
+        .and_then(|args| args.into_iter().next())
+        .is_some_and(|program| utils::find_executable(&program).is_none())
+}
+
+impl From<DesktopEntry> for Entry {
+    fn from(desktop_entry: DesktopEntry) -> Entry {
+        let kind = desktop_entry.kind().ok().flatten().unwrap_or_default();
+        let title = desktop_entry.get_localized("Desktop Entry", "Name").unwrap_or("Unnamed");
+        let untranslated_title = desktop_entry.get_string("Desktop Entry", "Name")
+            .filter(|name| *name != title);
+        let generic_name = desktop_entry.get_localized("Desktop Entry", "GenericName");
+        let description = desktop_entry.get_localized("Desktop Entry", "Comment");
+
+        let is_terminal = desktop_entry.get_bool("Desktop Entry", "Terminal");
+        let is_dbus_activatable = desktop_entry.get_bool("Desktop Entry", "DBusActivatable");
+        let startup_notify = desktop_entry.get_bool("Desktop Entry", "StartupNotify");
+        let startup_wm_class = desktop_entry.get_string("Desktop Entry", "StartupWMClass");
+        let working_dir = desktop_entry.get_string("Desktop Entry", "Path").filter(|path| !path.is_empty());
+
+        let icon = desktop_entry.get_string("Desktop Entry", "Icon").map(str::to_string);
+        let launch_command = desktop_entry.entry.get_raw("Desktop Entry", "Exec").map(str::to_string);
+
+        let is_missing_executable = is_missing_executable(launch_command.as_deref());
+        let keywords = desktop_entry.get_localized_list("Desktop Entry", "Keywords");
+        let categories = desktop_entry.get_list("Desktop Entry", "Categories");
+        let executable = executable_name(launch_command.as_deref());
 
         Entry::builder()
-            .id(&id)
+            .id(&desktop_entry.id)
+            .kind(kind)
             .title(title)
+            .untranslated_title(untranslated_title)
             .generic_name(generic_name)
             .description(description)
+            .keywords(keywords)
+            .categories(categories)
+            .executable(executable)
             .icon(icon)
             .launch_command(launch_command)
             .is_terminal(is_terminal)
+            .is_dbus_activatable(is_dbus_activatable)
+            .startup_notify(startup_notify)
+            .startup_wm_class(startup_wm_class)
+            .working_dir(working_dir)
+            .is_missing_executable(is_missing_executable)
             .user_confirm(false)
+            .source_path(Some(desktop_entry.path.clone()))
             .build()
     }
 }
 
-pub fn parse_desktop_entry(path: &PathBuf) -> Result<DesktopEntry, anyhow::Error> {
-    let entry = freedesktop_entry_parser::parse_entry(path)?;
+pub fn parse_desktop_entry(file: &DesktopFile) -> Result<DesktopEntry, anyhow::Error> {
+    let entry = KeyFile::parse_file(&file.path)?;
 
-    Ok(DesktopEntry { entry, path: path.clone() })
+    Ok(DesktopEntry { entry, id: file.id.clone(), path: file.path.clone() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A directory tree under the system temp directory, removed when dropped.
+    struct TempTree(PathBuf);
+
+    impl TempTree {
+        fn new(name: &str) -> Self {
+            let root = std::env::temp_dir().join(format!("yde-launcher-{}-{}", name, std::process::id()));
+            let _ = std::fs::remove_dir_all(&root);
+            std::fs::create_dir_all(&root).unwrap();
+            TempTree(root)
+        }
+
+        fn write(&self, path: &str, content: &str) -> PathBuf {
+            let path = self.0.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, content).unwrap();
+            path
+        }
+    }
+
+    impl Drop for TempTree {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    const EDITOR: &str = "[Desktop Entry]\nType=Application\nName=Editor\nExec=editor %F\n";
+
+    #[test]
+    fn ids_join_subdirectories_with_dashes() {
+        let apps_dir = Path::new("/usr/share/applications");
+        assert_eq!(desktop_file_id(apps_dir, &apps_dir.join("firefox.desktop")).as_deref(), Some("firefox.desktop"));
+        assert_eq!(desktop_file_id(apps_dir, &apps_dir.join("kde4/foo.desktop")).as_deref(), Some("kde4-foo.desktop"));
+        assert_eq!(desktop_file_id(apps_dir, &apps_dir.join("a/b/c.desktop")).as_deref(), Some("a-b-c.desktop"));
+        assert_eq!(desktop_file_id(apps_dir, Path::new("/opt/foo.desktop")), None);
+    }
+
+    #[test]
+    fn scan_recurses_into_subdirectories() {
+        let tree = TempTree::new("recursive");
+        tree.write("data/applications/kde4/foo.desktop", EDITOR);
+        tree.write("data/applications/editor.desktop", EDITOR);
+        tree.write("data/applications/README", "");
+
+        let files = scan_data_dirs(&[tree.0.join("data")]);
+        let ids: Vec<&str> = files.iter().map(|file| file.id.as_str()).collect();
+        assert_eq!(ids, ["editor.desktop", "kde4-foo.desktop"]);
+        assert!(files.iter().all(|file| file.apps_dir == tree.0.join("data/applications")));
+    }
+
+    #[test]
+    fn preferred_data_dir_shadows_the_same_id() {
+        let tree = TempTree::new("shadowing");
+        tree.write("system/applications/editor.desktop", EDITOR);
+        tree.write("system/applications/viewer.desktop", EDITOR);
+        let user_editor = tree.write("user/applications/editor.desktop", EDITOR);
+
+        let dirs = [tree.0.join("user"), tree.0.join("system")];
+        let files = shadow_duplicates(scan_data_dirs(&dirs));
+        let ids: Vec<(&str, &Path)> = files.iter().map(|file| (file.id.as_str(), file.path.as_path())).collect();
+        assert_eq!(ids, [
+            ("editor.desktop", user_editor.as_path()),
+            ("viewer.desktop", tree.0.join("system/applications/viewer.desktop").as_path()),
+        ]);
+    }
+
+    #[test]
+    fn hidden_user_file_masks_the_system_entry() {
+        let tree = TempTree::new("hidden");
+        tree.write("system/applications/editor.desktop", EDITOR);
+        tree.write("user/applications/editor.desktop", "[Desktop Entry]\nType=Application\nName=Editor\nHidden=true\n");
+
+        let dirs = [tree.0.join("user"), tree.0.join("system")];
+        let files = shadow_duplicates(scan_data_dirs(&dirs));
+        assert_eq!(files.len(), 1);
+        assert_eq!(evaluate_desktop_file(&files[0], &Config::default(), &[]).err(), Some(Exclusion::Hidden));
+    }
+
+    fn desktop_entry(content: &str) -> DesktopEntry {
+        DesktopEntry {
+            id: "editor.desktop".to_string(),
+            path: PathBuf::from("/usr/share/applications/editor.desktop"),
+            entry: KeyFile::parse(content).unwrap(),
+        }
+    }
+
+    fn desktops(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn shown_everywhere_without_either_key() {
+        let entry = desktop_entry("[Desktop Entry]\n");
+        assert!(entry.is_shown_in(&desktops(&["GNOME"])));
+        assert!(entry.is_shown_in(&[]));
+    }
+
+    #[test]
+    fn only_show_in_requires_a_listed_desktop() {
+        let entry = desktop_entry("[Desktop Entry]\nOnlyShowIn=KDE;LXQt;\n");
+        assert!(entry.is_shown_in(&desktops(&["KDE"])));
+        assert!(entry.is_shown_in(&desktops(&["ubuntu", "LXQt"])));
+        assert!(!entry.is_shown_in(&desktops(&["GNOME"])));
+        assert!(!entry.is_shown_in(&[]));
+    }
+
+    #[test]
+    fn not_show_in_hides_on_listed_desktops() {
+        let entry = desktop_entry("[Desktop Entry]\nNotShowIn=GNOME;\n");
+        assert!(!entry.is_shown_in(&desktops(&["GNOME"])));
+        assert!(!entry.is_shown_in(&desktops(&["ubuntu", "GNOME"])));
+        assert!(entry.is_shown_in(&desktops(&["KDE"])));
+        assert!(entry.is_shown_in(&[]));
+    }
+
+    #[test]
+    fn first_listed_current_desktop_decides_between_both_keys() {
+        let entry = desktop_entry("[Desktop Entry]\nOnlyShowIn=Unity;\nNotShowIn=GNOME;\n");
+        assert!(entry.is_shown_in(&desktops(&["Unity", "GNOME"])));
+        assert!(!entry.is_shown_in(&desktops(&["GNOME", "Unity"])));
+        // Neither key names the desktop, so OnlyShowIn being set hides it.
+        assert!(!entry.is_shown_in(&desktops(&["KDE"])));
+    }
 }
@@ -0,0 +1,397 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anyhow::{anyhow, bail};
+use zbus::zvariant::{OwnedValue, Value};
+
+use crate::entry::Entry;
+use crate::exec;
+use crate::startup::StartupToken;
+
+const APPLICATION_INTERFACE: &str = "org.freedesktop.Application";
+const PORTAL_BUS_NAME: &str = "org.freedesktop.portal.Desktop";
+const PORTAL_OBJECT_PATH: &str = "/org/freedesktop/portal/desktop";
+
+static REQUEST_SEQUENCE: AtomicUsize = AtomicUsize::new(0);
+
+/// Derives the well-known bus name from a desktop file ID (`org.gnome.Maps.desktop` → `org.gnome.Maps`).
+pub fn bus_name(desktop_id: &str) -> anyhow::Result<String> {
+    let name = desktop_id.strip_suffix(".desktop")
+        .ok_or_else(|| anyhow!("Desktop file ID {desktop_id:?} does not end in .desktop"))?;
+
+    let valid = name.split('.').count() >= 2
+        && name.split('.').all(|element| {
+            !element.is_empty()
+                && !element.starts_with(|c: char| c.is_ascii_digit())
+                && element.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        });
+    if !valid {
+        bail!("Desktop file ID {desktop_id:?} is not a valid D-Bus name");
+    }
+    Ok(name.to_string())
+}
+
+/// Derives the object path from a bus name (`org.gnome.Maps` → `/org/gnome/Maps`).
+pub fn object_path(bus_name: &str) -> String {
+    format!("/{}", bus_name.replace('.', "/").replace('-', "_"))
+}
+
+fn percent_encode_path(path: &str) -> String {
+    let mut out = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-_.~".contains(&byte) {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    out
+}
+
+fn target_as_uri(target: &str) -> String {
+    if exec::is_url(target) {
+        return target.to_string();
+    }
+    let path = PathBuf::from(target);
+    let path = if path.is_absolute() {
+        path
+    } else {
+        std::env::current_dir().map(|cwd| cwd.join(&path)).unwrap_or(path)
+    };
+    format!("file://{}", percent_encode_path(&path.to_string_lossy()))
+}
+
+fn platform_data(startup_token: Option<&StartupToken>) -> HashMap<&'static str, Value<'_>> {
+    let mut platform_data = HashMap::new();
+    match startup_token {
+        Some(StartupToken::Wayland(token)) => {
+            platform_data.insert("activation-token", Value::from(token.as_str()));
+            platform_data.insert("desktop-startup-id", Value::from(token.as_str()));
+        }
+        Some(StartupToken::X11(id)) => {
+            platform_data.insert("desktop-startup-id", Value::from(id.as_str()));
+        }
+        None => {}
+    }
+    platform_data
+}
+
+/// Starts an entry through the `org.freedesktop.Application` interface of its application.
+///
+/// Plain entries call `Activate`, or `Open` when launch targets are given. Desktop actions call
+/// `ActivateAction` on the application of their parent entry.
+pub fn activate(entry: &Entry, targets: &[String], startup_token: Option<&StartupToken>) -> anyhow::Result<()> {
+    activate_on(&zbus::blocking::Connection::session()?, entry, targets, startup_token)
+}
+
+fn activate_on(
+    connection: &zbus::blocking::Connection,
+    entry: &Entry,
+    targets: &[String],
+    startup_token: Option<&StartupToken>,
+) -> anyhow::Result<()> {
+    let (desktop_id, action) = match entry.parent_id() {
+        Some(parent_id) => {
+            let action = entry.id().strip_prefix(parent_id)
+                .and_then(|rest| rest.strip_prefix(':'))
+                .ok_or_else(|| anyhow!("Cannot determine the action name of {:?}", entry.id()))?;
+            (parent_id, Some(action))
+        }
+        None => (entry.id(), None),
+    };
+
+    let bus_name = bus_name(desktop_id)?;
+    let object_path = object_path(&bus_name);
+    let platform_data = platform_data(startup_token);
+
+    match action {
+        Some(action) => {
+            let parameter: Vec<Value> = Vec::new();
+            connection.call_method(
+                Some(bus_name.as_str()),
+                object_path.as_str(),
+                Some(APPLICATION_INTERFACE),
+                "ActivateAction",
+                &(action, parameter, platform_data),
+            )?;
+        }
+        None if !targets.is_empty() => {
+            let uris: Vec<String> = targets.iter().map(|t| target_as_uri(t)).collect();
+            connection.call_method(
+                Some(bus_name.as_str()),
+                object_path.as_str(),
+                Some(APPLICATION_INTERFACE),
+                "Open",
+                &(uris, platform_data),
+            )?;
+        }
+        None => {
+            connection.call_method(
+                Some(bus_name.as_str()),
+                object_path.as_str(),
+                Some(APPLICATION_INTERFACE),
+                "Activate",
+                &(platform_data,),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Opens a URI with the user's preferred application through the `OpenURI` desktop portal and
+/// waits for the portal's response. Dismissing the application chooser is not an error.
+pub fn open_uri(uri: &str) -> anyhow::Result<()> {
+    open_uri_on(&zbus::blocking::Connection::session()?, uri)
+}
+
+fn open_uri_on(connection: &zbus::blocking::Connection, uri: &str) -> anyhow::Result<()> {
+    // The response is sent as a signal on a request object whose path is derived from our
+    // unique name and a token of our choice, so it can be subscribed to before the call.
+    let handle_token = format!("yde_launcher_{}_{}", std::process::id(), REQUEST_SEQUENCE.fetch_add(1, Ordering::Relaxed));
+    let sender = connection.unique_name()
+        .ok_or_else(|| anyhow!("The D-Bus connection has no unique name"))?
+        .trim_start_matches(':')
+        .replace('.', "_");
+    let request_path = format!("{PORTAL_OBJECT_PATH}/request/{sender}/{handle_token}");
+    let request = zbus::blocking::proxy::Builder::<zbus::blocking::Proxy>::new(connection)
+        .destination(PORTAL_BUS_NAME)?
+        .path(request_path.as_str())?
+        .interface("org.freedesktop.portal.Request")?
+        .cache_properties(zbus::proxy::CacheProperties::No)
+        .build()?;
+    let mut responses = request.receive_signal("Response")?;
+
+    let options = HashMap::from([("handle_token", Value::from(handle_token.as_str()))]);
+    connection.call_method(
+        Some(PORTAL_BUS_NAME),
+        PORTAL_OBJECT_PATH,
+        Some("org.freedesktop.portal.OpenURI"),
+        "OpenURI",
+        &("", uri, options),
+    )?;
+
+    let response = responses.next().ok_or_else(|| anyhow!("The portal closed the request without responding"))?;
+    let (code, _results): (u32, HashMap<String, OwnedValue>) = response.body().deserialize()?;
+    match code {
+        0 | 1 => Ok(()),
+        _ => bail!("The portal could not open {uri:?}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader};
+    use std::process::{Child, Command, Stdio};
+    use std::sync::{Arc, Mutex};
+
+    use zbus::zvariant::OwnedObjectPath;
+
+    use super::*;
+
+    /// A private session bus, stopped when dropped.
+    struct Bus {
+        daemon: Child,
+        address: String,
+    }
+
+    impl Bus {
+        /// Starts `dbus-daemon`, or returns `None` when it is not installed.
+        fn start() -> Option<Self> {
+            let mut daemon = Command::new("dbus-daemon")
+                .args(["--session", "--nofork", "--nopidfile", "--print-address"])
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()
+                .ok()?;
+            let mut address = String::new();
+            BufReader::new(daemon.stdout.take()?).read_line(&mut address).ok()?;
+            Some(Bus { daemon, address: address.trim().to_string() })
+        }
+
+        fn connect(&self) -> zbus::blocking::Connection {
+            zbus::blocking::connection::Builder::address(self.address.as_str()).unwrap().build().unwrap()
+        }
+    }
+
+    impl Drop for Bus {
+        fn drop(&mut self) {
+            let _ = self.daemon.kill();
+            let _ = self.daemon.wait();
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Call {
+        method: &'static str,
+        arguments: Vec<String>,
+        activation_token: Option<String>,
+        startup_id: Option<String>,
+    }
+
+    #[derive(Default)]
+    struct MockApplication {
+        calls: Arc<Mutex<Vec<Call>>>,
+    }
+
+    impl MockApplication {
+        fn record(&self, method: &'static str, arguments: Vec<String>, platform_data: HashMap<String, OwnedValue>) {
+            let get = |key: &str| platform_data.get(key).and_then(|value| String::try_from(value.clone()).ok());
+            let (activation_token, startup_id) = (get("activation-token"), get("desktop-startup-id"));
+            self.calls.lock().unwrap().push(Call { method, arguments, activation_token, startup_id });
+        }
+    }
+
+    #[zbus::interface(name = "org.freedesktop.Application")]
+    impl MockApplication {
+        fn activate(&self, platform_data: HashMap<String, OwnedValue>) {
+            self.record("Activate", vec![], platform_data);
+        }
+
+        fn open(&self, uris: Vec<String>, platform_data: HashMap<String, OwnedValue>) {
+            self.record("Open", uris, platform_data);
+        }
+
+        fn activate_action(&self, action_name: String, _parameter: Vec<OwnedValue>, platform_data: HashMap<String, OwnedValue>) {
+            self.record("ActivateAction", vec![action_name], platform_data);
+        }
+    }
+
+    /// Starts a private bus with a mock `org.example.Editor` application and returns the calls it receives.
+    fn serve_mock(bus: &Bus) -> (zbus::blocking::Connection, Arc<Mutex<Vec<Call>>>) {
+        let application = MockApplication::default();
+        let calls = application.calls.clone();
+        let service = zbus::blocking::connection::Builder::address(bus.address.as_str()).unwrap()
+            .name("org.example.Editor").unwrap()
+            .serve_at("/org/example/Editor", application).unwrap()
+            .build().unwrap();
+        (service, calls)
+    }
+
+    fn editor() -> Entry {
+        Entry::builder()
+            .id("org.example.Editor.desktop")
+            .title("Text Editor")
+            .is_dbus_activatable(true)
+            .build()
+    }
+
+    #[test]
+    fn names_and_paths() {
+        assert_eq!(bus_name("org.gnome.Maps.desktop").unwrap(), "org.gnome.Maps");
+        assert_eq!(object_path("org.example.my-app"), "/org/example/my_app");
+        assert!(bus_name("firefox.desktop").is_err());
+        assert!(bus_name("org.2gis.Maps.desktop").is_err());
+    }
+
+    #[test]
+    fn activate_calls_the_application() {
+        let Some(bus) = Bus::start() else {
+            eprintln!("dbus-daemon is not installed, skipping");
+            return;
+        };
+        let (_service, calls) = serve_mock(&bus);
+        let connection = bus.connect();
+
+        let wayland_token = StartupToken::Wayland("token-1".to_string());
+        let x11_id = StartupToken::X11("startup-1".to_string());
+        let targets = ["https://example.com/".to_string(), "/tmp/a b.txt".to_string()];
+        activate_on(&connection, &editor(), &[], Some(&wayland_token)).unwrap();
+        activate_on(&connection, &editor(), &targets, Some(&x11_id)).unwrap();
+        let action = Entry::builder()
+            .id("org.example.Editor.desktop:new-window")
+            .parent_id(Some("org.example.Editor.desktop"))
+            .build();
+        activate_on(&connection, &action, &[], None).unwrap();
+
+        assert_eq!(*calls.lock().unwrap(), [
+            Call {
+                method: "Activate",
+                arguments: vec![],
+                activation_token: Some("token-1".to_string()),
+                startup_id: Some("token-1".to_string()),
+            },
+            Call {
+                method: "Open",
+                arguments: vec!["https://example.com/".to_string(), "file:///tmp/a%20b.txt".to_string()],
+                activation_token: None,
+                startup_id: Some("startup-1".to_string()),
+            },
+            Call { method: "ActivateAction", arguments: vec!["new-window".to_string()], activation_token: None, startup_id: None },
+        ]);
+    }
+
+    #[test]
+    fn activate_fails_without_the_application() {
+        let Some(bus) = Bus::start() else {
+            eprintln!("dbus-daemon is not installed, skipping");
+            return;
+        };
+        let entry = Entry::builder().id("org.example.Missing.desktop").build();
+        assert!(activate_on(&bus.connect(), &entry, &[], None).is_err());
+    }
+
+    /// A mock `OpenURI` portal that answers every request with `response`.
+    struct MockPortal {
+        response: u32,
+        uris: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[zbus::interface(name = "org.freedesktop.portal.OpenURI")]
+    impl MockPortal {
+        #[zbus(name = "OpenURI")]
+        async fn open_uri(
+            &self,
+            #[zbus(header)] header: zbus::message::Header<'_>,
+            #[zbus(connection)] connection: &zbus::Connection,
+            _parent_window: String,
+            uri: String,
+            options: HashMap<String, OwnedValue>,
+        ) -> zbus::fdo::Result<OwnedObjectPath> {
+            self.uris.lock().unwrap().push(uri);
+            let sender = header.sender().unwrap().trim_start_matches(':').replace('.', "_");
+            let token = options.get("handle_token").and_then(|token| String::try_from(token.clone()).ok()).unwrap();
+            let path = OwnedObjectPath::try_from(format!("{PORTAL_OBJECT_PATH}/request/{sender}/{token}")).unwrap();
+            let results: HashMap<String, OwnedValue> = HashMap::new();
+            connection.emit_signal(header.sender().map(|sender| sender.as_str()), &path, "org.freedesktop.portal.Request", "Response", &(self.response, results))
+                .await?;
+            Ok(path)
+        }
+    }
+
+    fn serve_portal(bus: &Bus, response: u32) -> (zbus::blocking::Connection, Arc<Mutex<Vec<String>>>) {
+        let uris = Arc::new(Mutex::new(Vec::new()));
+        let service = zbus::blocking::connection::Builder::address(bus.address.as_str()).unwrap()
+            .name(PORTAL_BUS_NAME).unwrap()
+            .serve_at(PORTAL_OBJECT_PATH, MockPortal { response, uris: uris.clone() }).unwrap()
+            .build().unwrap();
+        (service, uris)
+    }
+
+    #[test]
+    fn open_uri_waits_for_the_portal_response() {
+        let Some(bus) = Bus::start() else {
+            eprintln!("dbus-daemon is not installed, skipping");
+            return;
+        };
+        let (_service, uris) = serve_portal(&bus, 0);
+        let connection = bus.connect();
+        open_uri_on(&connection, "https://example.com/").unwrap();
+        open_uri_on(&connection, "https://example.org/").unwrap();
+        assert_eq!(*uris.lock().unwrap(), ["https://example.com/", "https://example.org/"]);
+    }
+
+    #[test]
+    fn open_uri_reports_portal_failures() {
+        let Some(bus) = Bus::start() else {
+            eprintln!("dbus-daemon is not installed, skipping");
+            return;
+        };
+        let connection = bus.connect();
+        assert!(open_uri_on(&connection, "https://example.com/").is_err());
+
+        let (_service, _uris) = serve_portal(&bus, 2);
+        let err = open_uri_on(&connection, "https://example.com/").unwrap_err();
+        assert_eq!(err.to_string(), "The portal could not open \"https://example.com/\"");
+    }
+}
@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::data::{self, DesktopFile, Exclusion};
+
+#[derive(Debug, Serialize)]
+pub struct FileReport {
+    #[serde(flatten)]
+    pub file: DesktopFile,
+    /// IDs of the entries this file provides when it is shown.
+    pub entries: Vec<String>,
+    pub excluded: Option<Exclusion>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub data_dirs: Vec<PathBuf>,
+    pub current_desktops: Vec<String>,
+    pub files: Vec<FileReport>,
+}
+
+/// Evaluates every scanned desktop file, including the ones shadowed by a preferred directory.
+pub fn build_report(config: &Config) -> Report {
+    let desktops = config.current_desktops();
+    let files = data::scan_desktop_files();
+
+    let mut winners: HashMap<&str, &DesktopFile> = HashMap::new();
+    for file in &files {
+        winners.entry(file.id.as_str()).or_insert(file);
+    }
+
+    let files = files.par_iter().map(|file| {
+        let winner = winners[file.id.as_str()];
+        let result = if winner.path != file.path {
+            Err(Exclusion::Duplicate { shadowed_by: winner.path.clone() })
+        } else {
+            data::evaluate_desktop_file(file, config, &desktops)
+        };
+        match result {
+            Ok(entries) => FileReport {
+                file: file.clone(),
+                entries: entries.iter().map(|entry| entry.id().to_string()).collect(),
+                excluded: None,
+            },
+            Err(exclusion) => FileReport {
+                file: file.clone(),
+                entries: vec![],
+                excluded: Some(exclusion),
+            },
+        }
+    }).collect();
+
+    Report {
+        data_dirs: data::get_data_dirs(),
+        current_desktops: desktops,
+        files,
+    }
+}
+
+fn print_text(report: &Report) {
+    println!("Data directories, most preferred first:");
+    for dir in &report.data_dirs {
+        println!("  {}", dir.display());
+    }
+    println!("Current desktops: {}", report.current_desktops.join(":"));
+    println!();
+
+    for file in &report.files {
+        let status = if file.excluded.is_some() { "excluded" } else { "shown" };
+        println!("{:<9} {}", status, file.file.id);
+        println!("          {}", file.file.path.display());
+        match &file.excluded {
+            Some(exclusion) => println!("          {}", exclusion),
+            None if file.entries.len() > 1 => println!("          {} desktop actions", file.entries.len() - 1),
+            None => {}
+        }
+    }
+
+    let shown = report.files.iter().filter(|file| file.excluded.is_none()).count();
+    println!();
+    println!("{} files scanned, {} shown, {} excluded", report.files.len(), shown, report.files.len() - shown);
+}
+
+/// Runs `yde-launcher doctor`, printing the report as text or, with `json`, as JSON.
+pub fn run(json: bool) -> anyhow::Result<()> {
+    let report = build_report(&Config::load());
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print_text(&report);
+    }
+    Ok(())
+}
@@ -1,28 +1,66 @@
+use std::path::{Path, PathBuf};
+
 use gpui::SharedString;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EntryKind {
+    /// Started through its launch command (`Type=Application` or a built-in action).
+    #[default]
+    Application,
+    /// Opens `url` with the default URL handler (`Type=Link`).
+    Link { url: String },
+}
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Entry {
     id: String,
+    parent_id: Option<String>,
+    kind: EntryKind,
     title: SharedString,
+    untranslated_title: Option<SharedString>,
     generic_name: Option<SharedString>,
     description: Option<SharedString>,
+    keywords: Vec<String>,
+    categories: Vec<String>,
+    executable: Option<String>,
     icon: Option<String>,
     launch_command: Option<String>,
     is_terminal: bool,
+    is_dbus_activatable: bool,
+    startup_notify: bool,
+    startup_wm_class: Option<String>,
+    working_dir: Option<PathBuf>,
     user_confirm: bool,
+    is_desktop_filtered: bool,
+    is_missing_executable: bool,
+    source_path: Option<PathBuf>,
 }
 
 impl Default for Entry {
     fn default() -> Self {
         Entry {
             id: String::new(),
+            parent_id: None,
+            kind: EntryKind::Application,
             title: SharedString::new("Unnamed"),
+            untranslated_title: None,
             generic_name: None,
             description: None,
+            keywords: Vec::new(),
+            categories: Vec::new(),
+            executable: None,
             icon: None,
             launch_command: None,
             is_terminal: false,
+            is_dbus_activatable: false,
+            startup_notify: false,
+            startup_wm_class: None,
+            working_dir: None,
             user_confirm: false,
+            is_desktop_filtered: false,
+            is_missing_executable: false,
+            source_path: None,
         }
     }
 }
@@ -31,6 +69,8 @@ pub struct EntryBuilder {
     entry: Entry,
 }
 
+// Setters are named after the fields they set, including the `is_*` flags.
+#[allow(clippy::wrong_self_convention)]
 impl EntryBuilder {
     pub fn new() -> Self {
         EntryBuilder {
@@ -43,11 +83,26 @@ impl EntryBuilder {
         self
     }
 
+    pub fn parent_id(mut self, parent_id: Option<impl Into<String>>) -> Self {
+        self.entry.parent_id = parent_id.map(|e|e.into());
+        self
+    }
+
+    pub fn kind(mut self, kind: EntryKind) -> Self {
+        self.entry.kind = kind;
+        self
+    }
+
     pub fn title(mut self, title: impl Into<String>) -> Self {
         self.entry.title = SharedString::from(title.into());
         self
     }
 
+    pub fn untranslated_title(mut self, untranslated_title: Option<impl Into<String>>) -> Self {
+        self.entry.untranslated_title = untranslated_title.map(|e|SharedString::from(e.into()));
+        self
+    }
+
     pub fn generic_name(mut self, generic_name: Option<impl Into<String>>) -> Self {
         self.entry.generic_name = generic_name.map(|e|SharedString::from(e.into()));
         self
@@ -58,6 +113,21 @@ impl EntryBuilder {
         self
     }
 
+    pub fn keywords(mut self, keywords: Vec<String>) -> Self {
+        self.entry.keywords = keywords;
+        self
+    }
+
+    pub fn categories(mut self, categories: Vec<String>) -> Self {
+        self.entry.categories = categories;
+        self
+    }
+
+    pub fn executable(mut self, executable: Option<impl Into<String>>) -> Self {
+        self.entry.executable = executable.map(|e|e.into());
+        self
+    }
+
     pub fn icon(mut self, icon: Option<impl Into<String>>) -> Self {
         self.entry.icon = icon.map(|e|e.into());
         self
@@ -73,11 +143,46 @@ impl EntryBuilder {
         self
     }
 
+    pub fn is_dbus_activatable(mut self, is_dbus_activatable: bool) -> Self {
+        self.entry.is_dbus_activatable = is_dbus_activatable;
+        self
+    }
+
+    pub fn startup_notify(mut self, startup_notify: bool) -> Self {
+        self.entry.startup_notify = startup_notify;
+        self
+    }
+
+    pub fn startup_wm_class(mut self, startup_wm_class: Option<impl Into<String>>) -> Self {
+        self.entry.startup_wm_class = startup_wm_class.map(|e|e.into());
+        self
+    }
+
+    pub fn working_dir(mut self, working_dir: Option<impl Into<PathBuf>>) -> Self {
+        self.entry.working_dir = working_dir.map(|e|e.into());
+        self
+    }
+
     pub fn user_confirm(mut self, must_confirm: bool) -> Self {
         self.entry.user_confirm = must_confirm;
         self
     }
 
+    pub fn is_desktop_filtered(mut self, is_desktop_filtered: bool) -> Self {
+        self.entry.is_desktop_filtered = is_desktop_filtered;
+        self
+    }
+
+    pub fn is_missing_executable(mut self, is_missing_executable: bool) -> Self {
+        self.entry.is_missing_executable = is_missing_executable;
+        self
+    }
+
+    pub fn source_path(mut self, source_path: Option<impl Into<PathBuf>>) -> Self {
+        self.entry.source_path = source_path.map(|e|e.into());
+        self
+    }
+
     pub fn build(self) -> Entry {
         self.entry
     }
@@ -87,15 +192,33 @@ impl Entry {
     pub fn id(&self) -> &str {
         &self.id
     }
+    pub fn parent_id(&self) -> Option<&str> {
+        self.parent_id.as_deref()
+    }
+    pub fn kind(&self) -> &EntryKind {
+        &self.kind
+    }
     pub fn title(&self) -> SharedString {
         self.title.clone()
     }
+    pub fn untranslated_title(&self) -> Option<SharedString> {
+        self.untranslated_title.clone()
+    }
     pub fn generic_name(&self) -> Option<SharedString> {
         self.generic_name.clone()
     }
     pub fn description(&self) -> Option<SharedString> {
         self.description.clone()
     }
+    pub fn keywords(&self) -> &[String] {
+        &self.keywords
+    }
+    pub fn categories(&self) -> &[String] {
+        &self.categories
+    }
+    pub fn executable(&self) -> Option<&str> {
+        self.executable.as_deref()
+    }
     pub fn icon(&self) -> Option<&str> {
         self.icon.as_deref()
     }
@@ -105,10 +228,34 @@ impl Entry {
     pub fn is_terminal(&self) -> bool {
         self.is_terminal
     }
+    pub fn is_dbus_activatable(&self) -> bool {
+        self.is_dbus_activatable
+    }
+    pub fn startup_notify(&self) -> bool {
+        self.startup_notify
+    }
+    pub fn startup_wm_class(&self) -> Option<&str> {
+        self.startup_wm_class.as_deref()
+    }
+    pub fn working_dir(&self) -> Option<&Path> {
+        self.working_dir.as_deref()
+    }
     pub fn user_confirm(&self) -> bool {
         self.user_confirm
     }
+    pub fn is_desktop_filtered(&self) -> bool {
+        self.is_desktop_filtered
+    }
+    pub fn is_missing_executable(&self) -> bool {
+        self.is_missing_executable
+    }
+    pub fn source_path(&self) -> Option<&Path> {
+        self.source_path.as_deref()
+    }
     pub fn builder() -> EntryBuilder {
         EntryBuilder::new()
     }
+    pub fn into_builder(self) -> EntryBuilder {
+        EntryBuilder { entry: self }
+    }
 }
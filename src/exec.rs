@@ -0,0 +1,301 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::bail;
+
+mod tokenizer;
+
+pub use tokenizer::parse_exec;
+
+/// Values that Exec field codes are substituted with.
+#[derive(Debug, Clone)]
+pub struct ExecContext<'a> {
+    pub icon: Option<&'a str>,
+    pub name: &'a str,
+    pub desktop_file: Option<&'a Path>,
+    pub targets: &'a [String],
+}
+
+pub fn is_url(target: &str) -> bool {
+    let Some((scheme, _)) = target.split_once(':') else {
+        return false;
+    };
+    let mut chars = scheme.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+}
+
+fn decode_percent(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len()
+            && bytes[i + 1].is_ascii_hexdigit() && bytes[i + 2].is_ascii_hexdigit()
+        {
+            let byte = u8::from_str_radix(&input[i + 1..i + 3], 16).unwrap_or(b'%');
+            out.push(byte);
+            i += 3;
+            continue;
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Converts a launch target into a local file name, if it refers to one.
+fn target_as_file(target: &str) -> Option<String> {
+    if let Some(path) = target.strip_prefix("file://") {
+        let path = path.find('/').map(|i| &path[i..])?;
+        return Some(decode_percent(path));
+    }
+    if is_url(target) {
+        return None;
+    }
+    Some(target.to_string())
+}
+
+fn target_as_url(target: &str) -> String {
+    if is_url(target) {
+        return target.to_string();
+    }
+    let path = PathBuf::from(target);
+    if path.is_absolute() {
+        return target.to_string();
+    }
+    std::env::current_dir()
+        .map(|cwd| cwd.join(path).to_string_lossy().into_owned())
+        .unwrap_or_else(|_| target.to_string())
+}
+
+fn single_code(args: &[String]) -> Option<char> {
+    args.iter().find_map(|arg| {
+        let mut chars = arg.chars();
+        while let Some(c) = chars.next() {
+            if c == '%' && let Some(code @ ('f' | 'u')) = chars.next() {
+                return Some(code);
+            }
+        }
+        None
+    })
+}
+
+fn expand_arg(arg: &str, ctx: &ExecContext, target: Option<&str>, out: &mut Vec<String>) -> anyhow::Result<()> {
+    let files = || ctx.targets.iter().filter_map(|t| target_as_file(t)).collect::<Vec<_>>();
+    let urls = || ctx.targets.iter().map(|t| target_as_url(t)).collect::<Vec<_>>();
+
+    match arg {
+        "%F" => {
+            out.extend(files());
+            return Ok(());
+        }
+        "%U" => {
+            out.extend(urls());
+            return Ok(());
+        }
+        "%i" => {
+            if let Some(icon) = ctx.icon.filter(|icon| !icon.is_empty()) {
+                out.push("--icon".to_string());
+                out.push(icon.to_string());
+            }
+            return Ok(());
+        }
+        "%f" | "%u" | "%k" | "%d" | "%D" | "%n" | "%N" | "%v" | "%m" => {
+            let value = expand_code(arg.as_bytes()[1] as char, ctx, target)?;
+            if !value.is_empty() {
+                out.push(value);
+            }
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    let mut expanded = String::with_capacity(arg.len());
+    let mut chars = arg.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            expanded.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('%') => expanded.push('%'),
+            Some(code @ ('F' | 'U' | 'i')) => {
+                bail!("Field code %{code} must be used as an argument on its own");
+            }
+            Some(code) => expanded.push_str(&expand_code(code, ctx, target)?),
+            None => bail!("Incomplete field code at the end of argument {arg:?}"),
+        }
+    }
+    out.push(expanded);
+    Ok(())
+}
+
+fn expand_code(code: char, ctx: &ExecContext, target: Option<&str>) -> anyhow::Result<String> {
+    Ok(match code {
+        'f' => target.and_then(target_as_file).unwrap_or_default(),
+        'u' => target.map(target_as_url).unwrap_or_default(),
+        'c' => ctx.name.to_string(),
+        'k' => ctx.desktop_file.map(|p| p.to_string_lossy().into_owned()).unwrap_or_default(),
+        // Deprecated field codes are removed from the command line.
+        'd' | 'D' | 'n' | 'N' | 'v' | 'm' => String::new(),
+        code => bail!("Unknown field code %{code}"),
+    })
+}
+
+/// Expands the field codes of an already tokenized Exec value.
+///
+/// Returns one argument vector per process to start: `%f` and `%u` only accept a
+/// single target, so the program is launched once for each target given.
+pub fn expand_field_codes(args: &[String], ctx: &ExecContext) -> anyhow::Result<Vec<Vec<String>>> {
+    if args.is_empty() {
+        bail!("Exec value is empty");
+    }
+
+    let targets: Vec<Option<&str>> = match single_code(args) {
+        Some(code) => {
+            let targets: Vec<&str> = ctx.targets.iter()
+                .map(|t| t.as_str())
+                .filter(|t| code != 'f' || target_as_file(t).is_some())
+                .collect();
+            if targets.is_empty() {
+                vec![None]
+            } else {
+                targets.into_iter().map(Some).collect()
+            }
+        }
+        None => vec![None],
+    };
+
+    targets.into_iter().map(|target| {
+        let mut out = Vec::with_capacity(args.len());
+        for arg in args {
+            expand_arg(arg, ctx, target, &mut out)?;
+        }
+        if out.is_empty() {
+            bail!("Exec value expands to an empty command");
+        }
+        Ok(out)
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DESKTOP_FILE: &str = "/usr/share/applications/org.example.Editor.desktop";
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    fn expand(exec: &[&str], targets: &[&str], icon: Option<&str>) -> anyhow::Result<Vec<Vec<String>>> {
+        let targets = strings(targets);
+        let ctx = ExecContext {
+            icon,
+            name: "Text Editor",
+            desktop_file: Some(Path::new(DESKTOP_FILE)),
+            targets: &targets,
+        };
+        expand_field_codes(&strings(exec), &ctx)
+    }
+
+    #[test]
+    fn single_file_without_targets_is_removed() {
+        assert_eq!(expand(&["editor", "%f"], &[], None).unwrap(), vec![strings(&["editor"])]);
+    }
+
+    #[test]
+    fn single_file_launches_once_per_target() {
+        assert_eq!(
+            expand(&["editor", "%f"], &["/tmp/a.txt", "file:///tmp/b%20c.txt"], None).unwrap(),
+            vec![strings(&["editor", "/tmp/a.txt"]), strings(&["editor", "/tmp/b c.txt"])],
+        );
+    }
+
+    #[test]
+    fn single_file_skips_remote_urls() {
+        assert_eq!(
+            expand(&["editor", "%f"], &["https://example.org/a.txt", "/tmp/a.txt"], None).unwrap(),
+            vec![strings(&["editor", "/tmp/a.txt"])],
+        );
+    }
+
+    #[test]
+    fn file_list_expands_to_every_target() {
+        assert_eq!(
+            expand(&["editor", "%F", "--new-window"], &["/tmp/a.txt", "file:///tmp/b.txt"], None).unwrap(),
+            vec![strings(&["editor", "/tmp/a.txt", "/tmp/b.txt", "--new-window"])],
+        );
+        assert_eq!(expand(&["editor", "%F"], &[], None).unwrap(), vec![strings(&["editor"])]);
+    }
+
+    #[test]
+    fn single_url_keeps_urls_and_paths() {
+        assert_eq!(
+            expand(&["browser", "%u"], &["https://example.org", "/tmp/page.html"], None).unwrap(),
+            vec![strings(&["browser", "https://example.org"]), strings(&["browser", "/tmp/page.html"])],
+        );
+        assert_eq!(expand(&["browser", "%u"], &[], None).unwrap(), vec![strings(&["browser"])]);
+    }
+
+    #[test]
+    fn url_list_expands_to_every_target() {
+        assert_eq!(
+            expand(&["browser", "%U"], &["https://example.org", "/tmp/page.html"], None).unwrap(),
+            vec![strings(&["browser", "https://example.org", "/tmp/page.html"])],
+        );
+        assert_eq!(expand(&["browser", "%U"], &[], None).unwrap(), vec![strings(&["browser"])]);
+    }
+
+    #[test]
+    fn icon_expands_to_two_arguments() {
+        assert_eq!(
+            expand(&["editor", "%i"], &[], Some("accessories-text-editor")).unwrap(),
+            vec![strings(&["editor", "--icon", "accessories-text-editor"])],
+        );
+        assert_eq!(expand(&["editor", "%i"], &[], None).unwrap(), vec![strings(&["editor"])]);
+        assert_eq!(expand(&["editor", "%i"], &[], Some("")).unwrap(), vec![strings(&["editor"])]);
+    }
+
+    #[test]
+    fn name_and_location_are_substituted() {
+        assert_eq!(
+            expand(&["editor", "--class=%c", "%k"], &[], None).unwrap(),
+            vec![strings(&["editor", "--class=Text Editor", DESKTOP_FILE])],
+        );
+    }
+
+    #[test]
+    fn location_is_removed_without_desktop_file() {
+        let ctx = ExecContext {
+            icon: None,
+            name: "Text Editor",
+            desktop_file: None,
+            targets: &[],
+        };
+        assert_eq!(expand_field_codes(&strings(&["editor", "%k"]), &ctx).unwrap(), vec![strings(&["editor"])]);
+    }
+
+    #[test]
+    fn percent_escape_and_deprecated_codes() {
+        assert_eq!(
+            expand(&["editor", "--zoom=100%%", "%d", "%N", "%v", "%m"], &[], None).unwrap(),
+            vec![strings(&["editor", "--zoom=100%"])],
+        );
+    }
+
+    #[test]
+    fn list_codes_must_stand_alone() {
+        let err = expand(&["editor", "--files=%F"], &["/tmp/a.txt"], None).unwrap_err();
+        assert_eq!(err.to_string(), "Field code %F must be used as an argument on its own");
+        assert!(expand(&["editor", "--urls=%U"], &[], None).is_err());
+        assert!(expand(&["editor", "--icon=%i"], &[], None).is_err());
+    }
+
+    #[test]
+    fn invalid_field_codes_are_errors() {
+        assert!(expand(&["editor", "%z"], &[], None).is_err());
+        assert!(expand(&["editor", "50%"], &[], None).is_err());
+        assert!(expand(&[], &[], None).is_err());
+    }
+}
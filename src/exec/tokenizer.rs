@@ -0,0 +1,109 @@
+use anyhow::bail;
+
+use crate::keyfile::unescape_string;
+
+/// Characters that must be quoted when they appear in an Exec argument.
+const RESERVED: &[char] = &[
+    ' ', '\t', '\n', '"', '\'', '\\', '>', '<', '~', '|', '&', ';', '$', '*', '?', '#', '(', ')', '`',
+];
+
+/// Characters that must be escaped with a backslash inside a quoted argument.
+const QUOTED_ESCAPABLE: &[char] = &['"', '`', '$', '\\'];
+
+/// Splits an unescaped Exec value into its arguments following the Desktop Entry quoting rules.
+pub fn split_exec(exec: &str) -> anyhow::Result<Vec<String>> {
+    let mut args = Vec::new();
+    let mut current: Option<String> = None;
+    let mut chars = exec.char_indices();
+
+    while let Some((pos, c)) = chars.next() {
+        match c {
+            ' ' => {
+                if let Some(arg) = current.take() {
+                    args.push(arg);
+                }
+            }
+            '"' => {
+                let arg = current.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((escape_pos, '\\')) => match chars.next() {
+                            Some((_, escaped)) if QUOTED_ESCAPABLE.contains(&escaped) => arg.push(escaped),
+                            Some((_, escaped)) => {
+                                bail!("Invalid escape sequence \\{escaped} at column {} of Exec value", escape_pos + 1);
+                            }
+                            None => bail!("Unterminated quoted argument starting at column {} of Exec value", pos + 1),
+                        },
+                        Some((_, quoted)) => arg.push(quoted),
+                        None => bail!("Unterminated quoted argument starting at column {} of Exec value", pos + 1),
+                    }
+                }
+            }
+            c if RESERVED.contains(&c) => {
+                bail!("Reserved character {c:?} at column {} of Exec value must be quoted", pos + 1);
+            }
+            c => current.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if let Some(arg) = current {
+        args.push(arg);
+    }
+
+    if args.is_empty() {
+        bail!("Exec value is empty");
+    }
+    Ok(args)
+}
+
+/// Parses a raw Exec value as read from a desktop file into its arguments.
+pub fn parse_exec(value: &str) -> anyhow::Result<Vec<String>> {
+    split_exec(&unescape_string(value)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_plain_arguments() {
+        assert_eq!(parse_exec("firefox  --new-window %u").unwrap(), ["firefox", "--new-window", "%u"]);
+    }
+
+    #[test]
+    fn quoted_arguments_keep_spaces_and_reserved_characters() {
+        assert_eq!(
+            parse_exec(r#""/opt/my app/run" --title="a|b (c)""#).unwrap(),
+            ["/opt/my app/run", "--title=a|b (c)"],
+        );
+    }
+
+    #[test]
+    fn escapes_inside_quotes() {
+        // As written in the file: the string escape `\\` is applied before the quoting rules.
+        assert_eq!(
+            parse_exec(r#"sh -c "echo \\$HOME \\"quoted\\" \\`cmd\\` C:\\\\data""#).unwrap(),
+            ["sh", "-c", r#"echo $HOME "quoted" `cmd` C:\data"#],
+        );
+    }
+
+    #[test]
+    fn string_escapes_are_applied_first() {
+        assert_eq!(parse_exec(r#"run "a\sb" c\sd"#).unwrap(), ["run", "a b", "c", "d"]);
+    }
+
+    #[test]
+    fn reserved_characters_must_be_quoted() {
+        let err = parse_exec("run $HOME").unwrap_err();
+        assert_eq!(err.to_string(), "Reserved character '$' at column 5 of Exec value must be quoted");
+        assert!(parse_exec("run a|b").is_err());
+        assert!(parse_exec("run 'single'").is_err());
+    }
+
+    #[test]
+    fn invalid_quoting_is_reported() {
+        assert!(parse_exec(r#"run "unterminated"#).is_err());
+        assert!(parse_exec(r#"run "\\a""#).is_err());
+        assert!(parse_exec("   ").is_err());
+    }
+}
@@ -0,0 +1,231 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use unicode_normalization::char::{decompose_compatible, is_combining_mark};
+
+use crate::config::Config;
+use crate::entry::Entry;
+use crate::matcher::{Match, Matcher};
+
+/// Letters without a canonical decomposition that are still commonly typed as plain Latin.
+const LATIN_FOLDS: &[(char, &str)] = &[
+    ('ß', "ss"), ('æ', "ae"), ('œ', "oe"), ('ø', "o"), ('đ', "d"), ('ð', "d"),
+    ('ł', "l"), ('þ', "th"), ('ı', "i"), ('ħ', "h"),
+];
+
+const CYRILLIC: &[(char, &str)] = &[
+    ('а', "a"), ('б', "b"), ('в', "v"), ('г', "g"), ('ґ', "g"), ('д', "d"), ('е', "e"),
+    ('ё', "e"), ('є', "ye"), ('ж', "zh"), ('з', "z"), ('и', "i"), ('і', "i"), ('ї', "yi"),
+    ('й', "y"), ('к', "k"), ('л', "l"), ('м', "m"), ('н', "n"), ('о', "o"), ('п', "p"),
+    ('р', "r"), ('с', "s"), ('т', "t"), ('у', "u"), ('ф', "f"), ('х', "kh"), ('ц', "ts"),
+    ('ч', "ch"), ('ш', "sh"), ('щ', "shch"), ('ъ', ""), ('ы', "y"), ('ь', ""), ('э', "e"),
+    ('ю', "yu"), ('я', "ya"),
+];
+
+const GREEK: &[(char, &str)] = &[
+    ('α', "a"), ('β', "v"), ('γ', "g"), ('δ', "d"), ('ε', "e"), ('ζ', "z"), ('η', "i"),
+    ('θ', "th"), ('ι', "i"), ('κ', "k"), ('λ', "l"), ('μ', "m"), ('ν', "n"), ('ξ', "x"),
+    ('ο', "o"), ('π', "p"), ('ρ', "r"), ('σ', "s"), ('ς', "s"), ('τ', "t"), ('υ', "y"),
+    ('φ', "f"), ('χ', "ch"), ('ψ', "ps"), ('ω', "o"),
+];
+
+/// A folded string together with the original character each of its characters came from.
+#[derive(Debug, Clone, Default)]
+pub struct Folded {
+    pub original: String,
+    pub text: String,
+    origins: Vec<usize>,
+}
+
+impl Folded {
+    /// Maps character indices into the folded text back to character indices of the original.
+    pub fn original_indices(&self, indices: &[usize]) -> Vec<usize> {
+        let mut original: Vec<usize> = indices.iter().filter_map(|&index| self.origins.get(index).copied()).collect();
+        original.dedup();
+        original
+    }
+}
+
+/// Folds text for matching: compatibility decomposition with combining marks removed, so
+/// "Editor de imágenes" becomes "Editor de imagenes", and optionally transliteration to Latin.
+#[derive(Debug, Clone, Default)]
+pub struct Folder {
+    table: HashMap<char, String>,
+}
+
+/// Reads a transliteration table: one `character=replacement` mapping per line, `#` comments.
+fn load_table(path: &Path, table: &mut HashMap<char, String>) -> anyhow::Result<()> {
+    for line in std::fs::read_to_string(path)?.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            anyhow::bail!("Expected character=replacement, found {:?}", line);
+        };
+        let mut chars = key.trim().chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => { table.insert(c, value.trim().to_string()); }
+            _ => anyhow::bail!("Expected a single character, found {:?}", key),
+        }
+    }
+    Ok(())
+}
+
+impl Folder {
+    pub fn new(config: &Config) -> Self {
+        let mut table: HashMap<char, String> = LATIN_FOLDS.iter()
+            .map(|(c, replacement)| (*c, replacement.to_string()))
+            .collect();
+        if config.transliterate {
+            table.extend(CYRILLIC.iter().chain(GREEK).map(|(c, replacement)| (*c, replacement.to_string())));
+        }
+        for path in &config.transliteration_tables {
+            if let Err(err) = load_table(path, &mut table) {
+                log::warn!("Failed to load transliteration table {:?}: {}", path, err);
+            }
+        }
+        Folder { table }
+    }
+
+    /// Looks up a replacement, keeping the case of uppercase letters in lowercase-only tables.
+    fn replacement(&self, c: char) -> Option<String> {
+        if let Some(replacement) = self.table.get(&c) {
+            return Some(replacement.clone());
+        }
+        let mut lower = c.to_lowercase();
+        let lower = match (lower.next(), lower.next()) {
+            (Some(lower), None) if lower != c => lower,
+            _ => return None,
+        };
+        let replacement = self.table.get(&lower)?;
+        let mut chars = replacement.chars();
+        Some(chars.next().map(|first| first.to_uppercase().chain(chars).collect()).unwrap_or_default())
+    }
+
+    pub fn fold(&self, text: &str) -> Folded {
+        let mut folded = Folded { original: text.to_string(), ..Default::default() };
+        for (index, c) in text.chars().enumerate() {
+            let mut push = |c: char| {
+                folded.text.push(c);
+                folded.origins.push(index);
+            };
+            // Table lookups come first so that e.g. "й" is not reduced to "и" before it is seen.
+            if let Some(replacement) = self.replacement(c) {
+                replacement.chars().for_each(&mut push);
+                continue;
+            }
+            decompose_compatible(c, |c| {
+                if is_combining_mark(c) {
+                    return;
+                }
+                match self.replacement(c) {
+                    Some(replacement) => replacement.chars().for_each(&mut push),
+                    None => push(c),
+                }
+            });
+        }
+        folded
+    }
+
+    pub fn fold_entry(&self, entry: &Entry) -> FoldedEntry {
+        FoldedEntry {
+            title: self.fold(&entry.title()),
+            untranslated_title: entry.untranslated_title().map(|title| self.fold(&title)),
+            generic_name: entry.generic_name().map(|name| self.fold(&name)),
+            keywords: entry.keywords().iter().map(|keyword| self.fold(keyword)).collect(),
+            categories: entry.categories().iter().map(|category| self.fold(category)).collect(),
+            executable: entry.executable().map(|executable| self.fold(executable)),
+        }
+    }
+}
+
+/// The searchable fields of an entry, folded once when the entries are loaded rather than for
+/// every query.
+#[derive(Debug, Clone, Default)]
+pub struct FoldedEntry {
+    pub title: Folded,
+    pub untranslated_title: Option<Folded>,
+    pub generic_name: Option<Folded>,
+    pub keywords: Vec<Folded>,
+    pub categories: Vec<Folded>,
+    pub executable: Option<Folded>,
+}
+
+/// Runs a matcher against folded fields and reports the indices of the original characters.
+pub struct FoldingMatcher {
+    pub inner: Box<dyn Matcher>,
+    /// Whether the matcher was built from a folded query. Regular expressions are not folded,
+    /// since that could change their meaning, and run on the original text instead.
+    pub folded: bool,
+}
+
+impl FoldingMatcher {
+    pub fn find(&self, field: &Folded) -> Option<Match> {
+        if !self.folded {
+            return self.inner.find(&field.original);
+        }
+        let found = self.inner.find(&field.text)?;
+        Some(Match {
+            score: found.score,
+            indices: field.original_indices(&found.indices),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matcher::{MatcherKind, new_matcher};
+
+    fn plain() -> Folder {
+        Folder::new(&Config::default())
+    }
+
+    fn transliterating() -> Folder {
+        Folder::new(&Config { transliterate: true, ..Default::default() })
+    }
+
+    #[test]
+    fn diacritics_are_removed() {
+        assert_eq!(plain().fold("Editor de imágenes").text, "Editor de imagenes");
+        assert_eq!(plain().fold("Ｆｉｒｅｆｏｘ").text, "Firefox");
+        assert_eq!(plain().fold("Straße").text, "Strasse");
+        assert_eq!(plain().fold("Œuvre").text, "Oeuvre");
+    }
+
+    #[test]
+    fn transliteration_is_optional() {
+        assert_eq!(plain().fold("Текст").text, "Текст");
+        assert_eq!(transliterating().fold("Текст").text, "Tekst");
+        assert_eq!(transliterating().fold("Щука").text, "Shchuka");
+        assert_eq!(transliterating().fold("Καλή").text, "Kali");
+        assert_eq!(transliterating().fold("й").text, "y");
+    }
+
+    #[test]
+    fn indices_map_back_to_the_original() {
+        let folded = plain().fold("Straße");
+        assert_eq!(folded.original, "Straße");
+        // "ss" both come from "ß".
+        assert_eq!(folded.original_indices(&[4, 5, 6]), vec![4, 5]);
+
+        let folded = transliterating().fold("Щука");
+        assert_eq!(folded.original_indices(&[0, 1, 2, 3, 4]), vec![0, 1]);
+        assert_eq!(folded.original_indices(&[42]), Vec::<usize>::new());
+
+        // Combining marks vanish, and the base letter stands for the whole character.
+        let folded = plain().fold("cafe\u{301} bar");
+        assert_eq!(folded.text, "cafe bar");
+        assert_eq!(folded.original_indices(&[3, 4, 5]), vec![3, 5, 6]);
+    }
+
+    #[test]
+    fn regex_matchers_run_on_the_original_text() {
+        let folded = plain().fold("Straße");
+        let plain = FoldingMatcher { inner: new_matcher(MatcherKind::Substring, "strass").unwrap(), folded: true };
+        assert_eq!(plain.find(&folded).map(|found| found.indices), Some(vec![0, 1, 2, 3, 4]));
+        let regex = FoldingMatcher { inner: new_matcher(MatcherKind::Regex, "ße$").unwrap(), folded: false };
+        assert_eq!(regex.find(&folded).map(|found| found.indices), Some(vec![4, 5]));
+    }
+}
@@ -0,0 +1,271 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::utils;
+
+/// Only the most recent launches of an entry are kept; older ones barely contribute anyway.
+const MAX_LAUNCHES_PER_ENTRY: usize = 32;
+/// The weight of a launch halves every week.
+const HALF_LIFE_SECS: f64 = 7.0 * 24.0 * 60.0 * 60.0;
+/// Scales the frecency bonus into the range of fuzzy match scores.
+const BONUS_SCALE: f64 = 20.0;
+/// Upper bound on the number of remembered query/entry pairs.
+const MAX_ASSOCIATIONS: usize = 2000;
+/// Queries longer than this are associated through their prefix of this length.
+const MAX_ASSOCIATION_QUERY_LEN: usize = 16;
+/// Associations fade more slowly than launches, as they only matter once the query is typed.
+const ASSOCIATION_HALF_LIFE_SECS: f64 = 30.0 * 24.0 * 60.0 * 60.0;
+/// Scales the association bonus so that a query's usual pick beats a slightly better match.
+const ASSOCIATION_BONUS_SCALE: f64 = 100.0;
+
+/// How strongly a query is associated with an entry, decayed lazily from `updated`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct Association {
+    strength: f64,
+    updated: u64,
+}
+
+impl Association {
+    fn strength_at(&self, now: u64) -> f64 {
+        self.strength * 0.5f64.powf(now.saturating_sub(self.updated) as f64 / ASSOCIATION_HALF_LIFE_SECS)
+    }
+}
+
+/// Launch timestamps per entry ID and the entries chosen for each query, persisted under
+/// `$XDG_STATE_HOME/yde-launcher`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct History {
+    launches: HashMap<String, Vec<u64>>,
+    #[serde(default)]
+    associations: HashMap<String, HashMap<String, Association>>,
+}
+
+/// The key a query is associated under: normalized and cut to `MAX_ASSOCIATION_QUERY_LEN`
+/// characters, the longest prefix that is recorded.
+fn association_key(query: &str) -> String {
+    query.trim().to_lowercase().chars().take(MAX_ASSOCIATION_QUERY_LEN).collect()
+}
+
+pub fn get_state_home() -> Option<PathBuf> {
+    utils::xdg_home("XDG_STATE_HOME", ".local/state")
+}
+
+pub fn get_history_path() -> Option<PathBuf> {
+    get_state_home().map(|dir| dir.join("yde-launcher").join("history.json"))
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+impl History {
+    pub fn load() -> Self {
+        let Some(path) = get_history_path() else {
+            return History::default();
+        };
+        let Ok(content) = std::fs::read(&path) else {
+            return History::default();
+        };
+        serde_json::from_slice(&content).unwrap_or_else(|err| {
+            log::warn!("Ignoring unreadable launch history {:?}: {}", path, err);
+            History::default()
+        })
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let Some(path) = get_history_path() else {
+            anyhow::bail!("Cannot determine the state directory");
+        };
+        utils::write_atomic(&path, &serde_json::to_vec(self)?)?;
+        Ok(())
+    }
+
+    pub fn record(&mut self, id: &str) {
+        let launches = self.launches.entry(id.to_string()).or_default();
+        launches.push(now());
+        if launches.len() > MAX_LAUNCHES_PER_ENTRY {
+            launches.drain(..launches.len() - MAX_LAUNCHES_PER_ENTRY);
+        }
+    }
+
+    /// Associates `id` with the query it was chosen for and with every prefix of that query,
+    /// so typing the start of the query is enough next time.
+    pub fn record_query(&mut self, query: &str, id: &str) {
+        self.record_query_at(query, id, now());
+    }
+
+    fn record_query_at(&mut self, query: &str, id: &str, now: u64) {
+        let query = association_key(query);
+        let prefixes = query.char_indices().map(|(index, c)| &query[..index + c.len_utf8()]);
+        for prefix in prefixes {
+            let association = self.associations.entry(prefix.to_string()).or_default()
+                .entry(id.to_string())
+                .or_insert(Association { strength: 0.0, updated: now });
+            association.strength = association.strength_at(now) + 1.0;
+            association.updated = now;
+        }
+        self.prune_associations(now);
+    }
+
+    /// Drops the weakest associations once the table grows past `MAX_ASSOCIATIONS`.
+    fn prune_associations(&mut self, now: u64) {
+        let count: usize = self.associations.values().map(HashMap::len).sum();
+        if count <= MAX_ASSOCIATIONS {
+            return;
+        }
+        let mut strengths: Vec<f64> = self.associations.values()
+            .flat_map(|ids| ids.values().map(|association| association.strength_at(now)))
+            .collect();
+        strengths.sort_by(|a, b| b.total_cmp(a));
+        let threshold = strengths[MAX_ASSOCIATIONS - 1];
+        let mut kept = 0;
+        for ids in self.associations.values_mut() {
+            ids.retain(|_, association| {
+                let keep = kept < MAX_ASSOCIATIONS && association.strength_at(now) >= threshold;
+                kept += keep as usize;
+                keep
+            });
+        }
+        self.associations.retain(|_, ids| !ids.is_empty());
+    }
+
+    /// The decayed strength with which the query has led to `id` being launched.
+    pub fn association(&self, query: &str, id: &str) -> f64 {
+        self.association_at(query, id, now())
+    }
+
+    fn association_at(&self, query: &str, id: &str, now: u64) -> f64 {
+        self.associations.get(&association_key(query))
+            .and_then(|ids| ids.get(id))
+            .map_or(0.0, |association| association.strength_at(now))
+    }
+
+    /// Removes an entry from the history. Returns whether it had been launched before.
+    pub fn forget(&mut self, id: &str) -> bool {
+        for ids in self.associations.values_mut() {
+            ids.remove(id);
+        }
+        self.associations.retain(|_, ids| !ids.is_empty());
+        self.launches.remove(id).is_some()
+    }
+
+    pub fn clear(&mut self) {
+        self.launches.clear();
+        self.associations.clear();
+    }
+
+    /// Frequency with time decay: every launch counts as 1 when it just happened and half as
+    /// much for every `HALF_LIFE_SECS` since.
+    pub fn frecency(&self, id: &str) -> f64 {
+        let now = now();
+        self.launches.get(id).map_or(0.0, |launches| {
+            launches.iter()
+                .map(|&time| 0.5f64.powf(now.saturating_sub(time) as f64 / HALF_LIFE_SECS))
+                .sum()
+        })
+    }
+
+    pub fn last_launch(&self, id: &str) -> Option<u64> {
+        self.launches.get(id).and_then(|launches| launches.last().copied())
+    }
+}
+
+/// The bonus added to a match score for a frecency. It grows logarithmically so that a daily
+/// favourite still loses against a much better match of the query.
+pub fn bonus(frecency: f64, weight: f64) -> i64 {
+    (frecency.ln_1p() * BONUS_SCALE * weight).round() as i64
+}
+
+/// The bonus added to a match score for the association between the query and an entry.
+pub fn association_bonus(strength: f64, weight: f64) -> i64 {
+    (strength.ln_1p() * ASSOCIATION_BONUS_SCALE * weight).round() as i64
+}
+
+/// Runs `yde-launcher clear-history`.
+pub fn run_clear() -> anyhow::Result<()> {
+    let mut history = History::load();
+    history.clear();
+    history.save()?;
+    println!("Launch history cleared");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn association_count(history: &History) -> usize {
+        history.associations.values().map(HashMap::len).sum()
+    }
+
+    #[test]
+    fn queries_are_associated_through_their_prefixes() {
+        let mut history = History::default();
+        history.record_query_at(" FireFox ", "firefox.desktop", NOW);
+        assert_eq!(history.association_at("firefox", "firefox.desktop", NOW), 1.0);
+        assert_eq!(history.association_at("fi", "firefox.desktop", NOW), 1.0);
+        assert_eq!(history.association_at("fox", "firefox.desktop", NOW), 0.0);
+        assert_eq!(history.association_at("fi", "chromium.desktop", NOW), 0.0);
+
+        history.record_query_at("fi", "firefox.desktop", NOW);
+        assert_eq!(history.association_at("fi", "firefox.desktop", NOW), 2.0);
+        assert_eq!(history.association_at("firefox", "firefox.desktop", NOW), 1.0);
+    }
+
+    #[test]
+    fn long_queries_are_associated_through_their_longest_prefix() {
+        let mut history = History::default();
+        let query = "libreoffice writer document";
+        history.record_query_at(query, "writer.desktop", NOW);
+        assert_eq!(association_count(&history), MAX_ASSOCIATION_QUERY_LEN);
+        assert_eq!(history.association_at(query, "writer.desktop", NOW), 1.0);
+        assert_eq!(history.association_at("libreoffice writer", "writer.desktop", NOW), 1.0);
+    }
+
+    #[test]
+    fn associations_decay() {
+        let mut history = History::default();
+        history.record_query_at("term", "terminal.desktop", NOW);
+        let half_life = ASSOCIATION_HALF_LIFE_SECS as u64;
+        assert!((history.association_at("term", "terminal.desktop", NOW + half_life) - 0.5).abs() < 1e-9);
+        assert!((history.association_at("term", "terminal.desktop", NOW + 2 * half_life) - 0.25).abs() < 1e-9);
+
+        // A new launch adds to the decayed strength.
+        history.record_query_at("term", "terminal.desktop", NOW + half_life);
+        assert!((history.association_at("term", "terminal.desktop", NOW + half_life) - 1.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn pruning_keeps_the_strongest_associations() {
+        let mut history = History::default();
+        history.record_query_at("a", "strong.desktop", NOW);
+        history.record_query_at("a", "strong.desktop", NOW);
+        for index in 0..MAX_ASSOCIATIONS {
+            history.record_query_at(&format!("q{index}"), "weak.desktop", NOW);
+        }
+        assert!(association_count(&history) <= MAX_ASSOCIATIONS);
+        assert_eq!(history.association_at("a", "strong.desktop", NOW), 2.0);
+    }
+
+    #[test]
+    fn forget_removes_launches_and_associations() {
+        let mut history = History::default();
+        history.record("firefox.desktop");
+        history.record_query_at("fire", "firefox.desktop", NOW);
+        history.record_query_at("fire", "fireplace.desktop", NOW);
+
+        assert!(history.forget("firefox.desktop"));
+        assert_eq!(history.frecency("firefox.desktop"), 0.0);
+        assert_eq!(history.association_at("fire", "firefox.desktop", NOW), 0.0);
+        assert_eq!(history.association_at("fire", "fireplace.desktop", NOW), 1.0);
+        assert!(!history.forget("firefox.desktop"));
+
+        history.forget("fireplace.desktop");
+        assert!(history.associations.is_empty());
+    }
+}
@@ -0,0 +1,47 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+fn find_icon(icon: &str, theme_name: Option<&str>) -> Option<PathBuf> {
+    let path_buf = PathBuf::from(icon);
+    if path_buf.is_absolute() {
+        return Some(path_buf);
+    }
+
+    if let Some(theme_name) = theme_name {
+        let icon_path = freedesktop_icons::lookup(icon)
+            .with_theme(theme_name)
+            .force_svg()
+            .with_size(64)
+            .find();
+        if icon_path.is_some() {
+            return icon_path;
+        }
+    }
+
+    freedesktop_icons::lookup(icon)
+        .with_theme("hicolor")
+        .force_svg()
+        .with_size(64)
+        .find()
+}
+
+/// Resolves icon names to files, using the current icon theme with hicolor as fallback.
+pub fn find_icons<'a>(icons: impl IntoIterator<Item = &'a str>) -> HashMap<String, PathBuf> {
+    let theme_name = linicon_theme::get_icon_theme();
+    let icons: Vec<&str> = icons.into_iter().collect();
+    icons.into_par_iter()
+        .filter_map(|icon| find_icon(icon, theme_name.as_deref()).map(|path| (icon.to_string(), path)))
+        .collect()
+}
+
+/// Directories whose contents affect icon lookups.
+pub fn get_icon_dirs(data_dirs: &[PathBuf]) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = data_dirs.iter().map(|dir| dir.join("icons")).collect();
+    if let Some(home) = std::env::home_dir() {
+        dirs.insert(0, home.join(".icons"));
+    }
+    dirs.push(PathBuf::from("/usr/share/pixmaps"));
+    dirs
+}
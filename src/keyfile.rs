@@ -0,0 +1,209 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+/// A value as written in the file and with the escape sequences of the `string` type applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Value {
+    raw: String,
+    unescaped: String,
+}
+
+/// A parsed desktop entry style file: groups of `key=value` pairs. Values are kept as written,
+/// since a backslash starts an escape sequence whose meaning depends on the type of the key.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeyFile {
+    groups: HashMap<String, HashMap<String, Value>>,
+}
+
+/// A line that is neither blank, a comment, a group header nor a key-value pair. Displays as
+/// the message alone, the line number is reported separately.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Applies the escape sequences of the `string` value type (`\s`, `\n`, `\t`, `\r` and `\\`).
+pub fn unescape_string(value: &str) -> anyhow::Result<String> {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => out.push(' '),
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('r') => out.push('\r'),
+            Some('\\') => out.push('\\'),
+            // Other sequences belong to the Exec quoting rules and are kept for the tokenizer.
+            Some(other) => {
+                out.push('\\');
+                out.push(other);
+            }
+            None => anyhow::bail!("Trailing backslash in string value"),
+        }
+    }
+    Ok(out)
+}
+
+/// Splits a list value at every `;` that is not escaped as `\;`.
+fn split_list(raw: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut current = String::new();
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            ';' => items.push(std::mem::take(&mut current)),
+            '\\' => match chars.next() {
+                Some(';') => current.push(';'),
+                Some(other) => {
+                    current.push('\\');
+                    current.push(other);
+                }
+                None => current.push('\\'),
+            },
+            c => current.push(c),
+        }
+    }
+    items.push(current);
+    items
+}
+
+impl KeyFile {
+    pub fn parse(content: &str) -> Result<Self, ParseError> {
+        let mut key_file = KeyFile::default();
+        let mut group: Option<&mut HashMap<String, Value>> = None;
+        for (index, line) in content.lines().enumerate() {
+            let error = |message: &str| ParseError { line: index + 1, message: message.to_string() };
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = trimmed.strip_prefix('[') {
+                let name = name.strip_suffix(']').ok_or_else(|| error("unterminated group header"))?;
+                if name.contains(['[', ']']) {
+                    return Err(error("invalid group name"));
+                }
+                group = Some(key_file.groups.entry(name.to_string()).or_default());
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(error("expected a group header or a key=value pair"));
+            };
+            let key = key.trim();
+            if key.is_empty() {
+                return Err(error("missing key before ="));
+            }
+            let group = group.as_mut().ok_or_else(|| error("key-value pair before the first group header"))?;
+            let raw = value.trim_start().to_string();
+            // A malformed trailing backslash is kept rather than rejecting the whole file.
+            let unescaped = unescape_string(&raw).unwrap_or_else(|_| raw.clone());
+            // Keys may not be repeated; the first occurrence wins.
+            group.entry(key.to_string()).or_insert(Value { raw, unescaped });
+        }
+        Ok(key_file)
+    }
+
+    pub fn parse_file(path: &Path) -> anyhow::Result<Self> {
+        Ok(Self::parse(&std::fs::read_to_string(path)?)?)
+    }
+
+    fn value(&self, group: &str, key: &str) -> Option<&Value> {
+        self.groups.get(group)?.get(key)
+    }
+
+    /// The value with escape sequences applied, for `string`, `localestring` and `boolean` keys.
+    pub fn get(&self, group: &str, key: &str) -> Option<&str> {
+        self.value(group, key).map(|value| value.unescaped.as_str())
+    }
+
+    /// The value as written, for keys such as `Exec` that have their own quoting rules.
+    pub fn get_raw(&self, group: &str, key: &str) -> Option<&str> {
+        self.value(group, key).map(|value| value.raw.as_str())
+    }
+
+    /// The non-empty items of a `;`-separated list value, with escape sequences applied.
+    pub fn get_list(&self, group: &str, key: &str) -> Vec<String> {
+        let Some(value) = self.value(group, key) else {
+            return vec![];
+        };
+        split_list(&value.raw).into_iter()
+            .filter(|item| !item.is_empty())
+            .map(|item| unescape_string(&item).unwrap_or(item))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backslashes_are_kept_in_raw_values() {
+        let file = KeyFile::parse(concat!(
+            "[Desktop Entry]\n",
+            r#"Exec="/opt/my app/run" --dir "C:\\\\data" \$HOME"#, "\n",
+        )).unwrap();
+        assert_eq!(file.get_raw("Desktop Entry", "Exec"), Some(r#""/opt/my app/run" --dir "C:\\\\data" \$HOME"#));
+    }
+
+    #[test]
+    fn exec_values_reach_the_tokenizer_intact() {
+        let file = KeyFile::parse(concat!(
+            "[Desktop Entry]\n",
+            r#"Exec="/opt/my app/run" --dir "C:\\\\data" "\\$HOME""#, "\n",
+        )).unwrap();
+        let exec = file.get_raw("Desktop Entry", "Exec").unwrap();
+        assert_eq!(crate::exec::parse_exec(exec).unwrap(), ["/opt/my app/run", "--dir", r"C:\data", "$HOME"]);
+    }
+
+    #[test]
+    fn string_escapes_are_applied() {
+        let file = KeyFile::parse("[Desktop Entry]\nComment=a\\sb\\\\c\\td\\ne\n").unwrap();
+        assert_eq!(file.get("Desktop Entry", "Comment"), Some("a b\\c\td\ne"));
+    }
+
+    #[test]
+    fn lists_split_at_unescaped_semicolons() {
+        let file = KeyFile::parse("[Desktop Entry]\nKeywords=text;a\\;b;;c\\sd;\n").unwrap();
+        assert_eq!(file.get_list("Desktop Entry", "Keywords"), vec!["text", "a;b", "c d"]);
+        assert!(file.get_list("Desktop Entry", "Categories").is_empty());
+    }
+
+    #[test]
+    fn groups_comments_and_whitespace() {
+        let file = KeyFile::parse(concat!(
+            "# comment\n",
+            "\n",
+            "[Desktop Entry]\n",
+            "Name = Editor\n",
+            "Name[de]=Bearbeiter\n",
+            "Name=Shadowed\n",
+            "[Desktop Action new-window]\n",
+            "Name=New Window\n",
+        )).unwrap();
+        assert_eq!(file.get("Desktop Entry", "Name"), Some("Editor"));
+        assert_eq!(file.get("Desktop Entry", "Name[de]"), Some("Bearbeiter"));
+        assert_eq!(file.get("Desktop Action new-window", "Name"), Some("New Window"));
+        assert_eq!(file.get("Desktop Entry", "Missing"), None);
+    }
+
+    #[test]
+    fn invalid_lines_report_their_number() {
+        let err = KeyFile::parse("[Desktop Entry]\nName=Editor\nnot a pair\n").unwrap_err();
+        assert_eq!(err.line, 3);
+        assert_eq!(KeyFile::parse("Name=Editor\n").unwrap_err().line, 1);
+        assert_eq!(KeyFile::parse("[Desktop Entry\n").unwrap_err().line, 1);
+    }
+}
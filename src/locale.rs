@@ -0,0 +1,75 @@
+use std::sync::LazyLock;
+
+static LOCALE_CANDIDATES: LazyLock<Vec<String>> = LazyLock::new(|| {
+    let locale = ["LC_ALL", "LC_MESSAGES", "LANG"].iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty());
+
+    locale.map(|locale| locale_candidates(&locale)).unwrap_or_default()
+});
+
+/// Returns the locale suffixes to try for localized keys, most specific first.
+pub fn current_locale_candidates() -> &'static [String] {
+    &LOCALE_CANDIDATES
+}
+
+/// Expands a `lang_COUNTRY.ENCODING@MODIFIER` locale into the matching order from the
+/// Desktop Entry specification. The encoding part is never used for matching.
+pub fn locale_candidates(locale: &str) -> Vec<String> {
+    let (rest, modifier) = match locale.split_once('@') {
+        Some((rest, modifier)) => (rest, Some(modifier)),
+        None => (locale, None),
+    };
+    let rest = rest.split_once('.').map(|(rest, _encoding)| rest).unwrap_or(rest);
+    let (lang, country) = match rest.split_once('_') {
+        Some((lang, country)) => (lang, Some(country)),
+        None => (rest, None),
+    };
+
+    if lang.is_empty() || lang == "C" || lang == "POSIX" {
+        return vec![];
+    }
+
+    let mut candidates = Vec::with_capacity(4);
+    if let (Some(country), Some(modifier)) = (country, modifier) {
+        candidates.push(format!("{lang}_{country}@{modifier}"));
+    }
+    if let Some(country) = country {
+        candidates.push(format!("{lang}_{country}"));
+    }
+    if let Some(modifier) = modifier {
+        candidates.push(format!("{lang}@{modifier}"));
+    }
+    candidates.push(lang.to_string());
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn full_locale_falls_back_in_spec_order() {
+        assert_eq!(locale_candidates("sr_YU@Latn"), ["sr_YU@Latn", "sr_YU", "sr@Latn", "sr"]);
+    }
+
+    #[test]
+    fn encoding_is_ignored() {
+        assert_eq!(locale_candidates("de_DE.UTF-8"), ["de_DE", "de"]);
+        assert_eq!(locale_candidates("sr_YU.UTF-8@Latn"), ["sr_YU@Latn", "sr_YU", "sr@Latn", "sr"]);
+    }
+
+    #[test]
+    fn language_only() {
+        assert_eq!(locale_candidates("fr"), ["fr"]);
+        assert_eq!(locale_candidates("ca@valencia"), ["ca@valencia", "ca"]);
+    }
+
+    #[test]
+    fn untranslated_locales_have_no_candidates() {
+        assert!(locale_candidates("C").is_empty());
+        assert!(locale_candidates("C.UTF-8").is_empty());
+        assert!(locale_candidates("POSIX").is_empty());
+        assert!(locale_candidates("").is_empty());
+    }
+}
@@ -1,8 +1,6 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::{PathBuf};
 use std::rc::Rc;
-use fuzzy_matcher::skim::SkimMatcherV2;
-use fuzzy_matcher::FuzzyMatcher;
 use gpui::prelude::FluentBuilder;
 use gpui::*;
 use gpui_component::button::{Button, ButtonVariants};
@@ -10,28 +8,75 @@ use gpui_component::scroll::ScrollHandleOffsetable;
 use gpui_component::{Kbd, Root, Theme, VirtualListScrollHandle, v_virtual_list};
 use gpui_component::input::{InputEvent, InputState, TextInput};
 use gpui_component::Selectable;
-use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use futures::StreamExt;
 
 mod data;
+mod dbus;
+mod doctor;
 mod entry;
 mod utils;
 mod actions;
+mod cache;
+mod categories;
+mod config;
+mod exec;
+mod fold;
+mod history;
+mod icons;
+mod keyfile;
+mod locale;
+mod matcher;
+mod query;
+mod search;
+mod startup;
+mod watcher;
 
-use crate::data::DesktopEntry;
-use crate::entry::Entry;
+use crate::config::Config;
+use crate::entry::{Entry, EntryKind};
+use crate::exec::ExecContext;
+use crate::fold::{FoldedEntry, Folder, FoldingMatcher};
+use crate::history::History;
 use crate::utils::WithAlpha;
 
+/// Typo-tolerant matching only kicks in when the query matches fewer entries than this.
+const MIN_MATCHES_BEFORE_SUGGESTIONS: usize = 3;
+
+/// A row of the result list: a category section header, the header above typo-tolerant
+/// results, or an index into `filtered_entries`.
+#[derive(Debug, Clone)]
+enum ListItem {
+    Header { category: &'static str, count: usize, collapsed: bool },
+    Suggestions,
+    Entry(usize),
+}
+
 #[derive(Clone)]
 pub struct State {
     selection_index: usize,
     query: String,
     input_focus_handle: Option<FocusHandle>,
     entries: Vec<Entry>,
+    /// The searchable fields of `entries`, folded once and indexed alike.
+    folded_entries: Vec<FoldedEntry>,
     filtered_entries: Vec<Entry>,
+    /// Matched characters of each filtered entry, indexed like `filtered_entries`.
+    filtered_highlights: Vec<search::Highlights>,
+    /// Index into `filtered_entries` where the typo-tolerant "did you mean" results begin.
+    suggestions_start: usize,
+    list_items: Vec<ListItem>,
+    collapsed_categories: HashSet<&'static str>,
     scroll_handle: VirtualListScrollHandle,
     scroll_view_bounds: Option<Bounds<Pixels>>,
     icon_map: HashMap<String, PathBuf>,
     confirming_entry: Option<Entry>,
+    /// Set while a D-Bus activation or a Link is in flight, so that it is not started twice.
+    launching: bool,
+    launch_targets: Vec<String>,
+    launch_error: Option<String>,
+    query_error: Option<String>,
+    config: Config,
+    folder: Folder,
+    history: History,
 }
 
 impl Default for State {
@@ -41,159 +86,459 @@ impl Default for State {
             query: String::new(),
             input_focus_handle: None,
             entries: Vec::new(),
+            folded_entries: Vec::new(),
             filtered_entries: Vec::new(),
+            filtered_highlights: Vec::new(),
+            suggestions_start: 0,
+            list_items: Vec::new(),
+            collapsed_categories: HashSet::new(),
             scroll_handle: VirtualListScrollHandle::new(),
             icon_map: HashMap::new(),
             scroll_view_bounds: None,
             confirming_entry: None,
+            launching: false,
+            launch_targets: Vec::new(),
+            launch_error: None,
+            query_error: None,
+            config: Config::default(),
+            folder: Folder::default(),
+            history: History::default(),
         }
     }
 }
 
 impl Global for State {}
 
+/// Renders text with the matched characters emphasized.
+/// Opens a URL through the desktop portal, falling back to `xdg-open` when there is none.
+/// Blocks until the URL has been handed to an application.
+fn open_url(url: &str) -> anyhow::Result<()> {
+    match dbus::open_uri(url) {
+        Ok(()) => return Ok(()),
+        Err(err) => log::warn!("Opening {} through the desktop portal failed, falling back to xdg-open: {}", url, err),
+    }
+    let status = std::process::Command::new("xdg-open").arg(url).status()?;
+    if !status.success() {
+        anyhow::bail!("xdg-open {}", status);
+    }
+    Ok(())
+}
+
+fn highlighted_text(text: SharedString, indices: &[usize]) -> StyledText {
+    let style = HighlightStyle {
+        color: Some(rgb(0x89dceb).into()),
+        font_weight: Some(FontWeight::BOLD),
+        ..Default::default()
+    };
+    let ranges = search::highlight_ranges(&text, indices);
+    StyledText::new(text).with_highlights(ranges.into_iter().map(|range| (range, style)))
+}
+
 impl State {
     fn find_icons(&mut self) {
-        let theme_name = linicon_theme::get_icon_theme();
-        let entries = &self.entries;
-        self.icon_map.clear();
-        let iter: Vec<(String, PathBuf)> = entries.par_iter().map(|entry|{
-            let icon = entry.icon();
-            if let Some(icon) = icon {
-                let path_buf = PathBuf::from(icon);
-                if path_buf.is_absolute() {
-                    return Some((icon.to_string(), path_buf));
+        let icons: HashSet<&str> = self.entries.iter().filter_map(|entry| entry.icon()).collect();
+        self.icon_map = icons::find_icons(icons);
+    }
+    fn set_entries(&mut self, desktop_entries: Vec<Entry>) {
+        self.entries = desktop_entries;
+        self.entries.extend(actions::get_builtin_actions());
+        self.fold_entries();
+        self.update_filtered_entries();
+    }
+    fn fold_entries(&mut self) {
+        self.folded_entries = self.entries.iter().map(|entry| self.folder.fold_entry(entry)).collect();
+    }
+    /// Scans the desktop entries, stores them in the cache and returns them.
+    fn refresh_entries(&mut self) -> Vec<Entry> {
+        // The key is taken before scanning so that changes made during the scan invalidate it.
+        let cache_key = cache::current_key(&self.config);
+        let desktop_entries = data::get_desktop_entries(&self.config);
+        self.set_entries(desktop_entries.clone());
+        self.find_icons();
+        cache::store(cache_key, &desktop_entries, &self.icon_map);
+        desktop_entries
+    }
+    fn apply_updates(&mut self, updates: Vec<watcher::Update>) {
+        for update in updates {
+            match update {
+                watcher::Update::Reload { entries, icon_map } => {
+                    self.set_entries(entries);
+                    self.icon_map = icon_map;
                 }
+                update => update.apply_to(&mut self.entries, &mut self.icon_map),
+            }
+        }
+        self.fold_entries();
+        self.update_filtered_entries();
+        let index = self.selection_index.min(self.list_items.len().saturating_sub(1));
+        self.selection_index = self.nearest_selectable_index(index, true);
+    }
+    /// Ranks the entries accepted by `keep` that match every query token, best first.
+    fn rank_entries(&self, matchers: Option<&[FoldingMatcher]>, keep: &dyn Fn(usize, &Entry) -> bool) -> Vec<usize> {
+        let weights = &self.config.search_weights;
+        let mut vec = self.entries.iter().enumerate().filter(|(index, entry)| keep(*index, entry)).flat_map(|(index, entry)| {
+            let score = match matchers {
+                Some(matchers) => search::score_entry(matchers, &self.folded_entries[index], weights),
+                None => Some(0),
+            };
+            score.map(|score| {
+                let frecency = self.history.frecency(entry.id());
+                let association = self.history.association(&self.query, entry.id());
+                let score = score
+                    + history::bonus(frecency, weights.frecency)
+                    + history::association_bonus(association, weights.association);
+                (score, frecency, self.history.last_launch(entry.id()), index)
+            })
+        }).collect::<Vec<_>>();
+        // Ties go to the more frecent entry, then the more recently launched one, then by title.
+        vec.sort_by(|a, b| {
+            b.0.cmp(&a.0)
+                .then_with(|| b.1.total_cmp(&a.1))
+                .then_with(|| b.2.cmp(&a.2))
+                .then_with(|| self.entries[a.3].title().as_str().cmp(self.entries[b.3].title().as_str()))
+        });
+        vec.into_iter().map(|(_, _, _, index)| index).collect()
+    }
+    fn update_filtered_entries(&mut self) {
+        let parsed = query::parse(&self.query, self.config.matcher).and_then(|query| {
+            let tokens: Vec<String> = matcher::split_tokens(query.matcher, &query.text).into_iter().map(str::to_string).collect();
+            // Regular expressions are matched against the original text, everything else is folded.
+            let folded = query.matcher != matcher::MatcherKind::Regex;
+            let matchers = tokens.iter()
+                .map(|token| {
+                    let token = if folded { self.folder.fold(token).text } else { token.clone() };
+                    matcher::new_matcher(query.matcher, &token).map(|inner| FoldingMatcher { inner, folded })
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            let exclusions = query.excluded.iter()
+                .map(|word| matcher::new_matcher(matcher::MatcherKind::Substring, &self.folder.fold(word).text)
+                    .map(|inner| FoldingMatcher { inner, folded: true }))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            Ok((query, tokens, matchers, exclusions))
+        });
+        let (query, tokens, matchers, exclusions) = match parsed {
+            Ok(parsed) => parsed,
+            Err(err) => {
+                self.query_error = Some(err.to_string());
+                self.filtered_entries = vec![];
+                self.filtered_highlights = vec![];
+                self.suggestions_start = 0;
+                self.update_list_items();
+                return;
+            }
+        };
+        self.query_error = None;
+        let kind = query.matcher;
+        let matchers = if matchers.is_empty() { None } else { Some(matchers) };
 
-                if let Some(theme_name) = &theme_name {
-                    let icon_path = freedesktop_icons::lookup(icon)
-                        .with_theme(&theme_name)
-                        .force_svg()
-                        .with_size(64)
-                        .find();
-                    if let Some(icon_path) = icon_path {
-                        return Some((icon.to_string(), icon_path));
-                    }
-                }
+        let weights = &self.config.search_weights;
+        let accepts = |index: usize, entry: &Entry| query.accepts(entry)
+            && !exclusions.iter().any(|exclusion| search::score_entry(std::slice::from_ref(exclusion), &self.folded_entries[index], weights).is_some());
+        let ranked = self.rank_entries(matchers.as_deref(), &accepts);
+        let mut highlights: Vec<search::Highlights> = match &matchers {
+            Some(matchers) => ranked.iter().map(|&index| search::highlights(matchers, &self.folded_entries[index])).collect(),
+            None => vec![search::Highlights::default(); ranked.len()],
+        };
+        let mut suggestions = vec![];
 
-                let icon_path = freedesktop_icons::lookup(icon)
-                    .with_theme("hicolor")
-                    .force_svg()
-                    .with_size(64)
-                    .find();
-                if let Some(icon_path) = icon_path {
-                    return Some((icon.to_string(), icon_path));
-                }
+        // When the query finds (almost) nothing, look again allowing typos. Regular expressions
+        // are taken literally.
+        if matchers.is_some() && ranked.len() < MIN_MATCHES_BEFORE_SUGGESTIONS && kind != matcher::MatcherKind::Regex {
+            let typo_matchers: anyhow::Result<Vec<FoldingMatcher>> = tokens.iter()
+                .map(|token| {
+                    let token = self.folder.fold(token).text;
+                    matcher::new_matcher(kind, &token)
+                        .map(|primary| FoldingMatcher { inner: matcher::with_typo_fallback(primary, &token), folded: true })
+                })
+                .collect();
+            if let Ok(typo_matchers) = typo_matchers {
+                let matched: HashSet<usize> = ranked.iter().copied().collect();
+                suggestions = self.rank_entries(Some(typo_matchers.as_slice()), &|index, entry| !matched.contains(&index) && accepts(index, entry));
+                highlights.extend(suggestions.iter().map(|&index| search::highlights(&typo_matchers, &self.folded_entries[index])));
             }
-            return None;
-        }).flatten().collect();
-        for (icon_name, icon_path) in iter {
-            self.icon_map.insert(icon_name, icon_path);
         }
-    }
-    fn refresh_entries(&mut self) {
-        self.entries = vec![];
-        self.entries.extend(data::get_desktop_entries());
-        self.entries.extend(actions::get_builtin_actions());
 
+        self.suggestions_start = ranked.len();
+        self.filtered_entries = ranked.into_iter().chain(suggestions).map(|index| self.entries[index].clone()).collect();
+        self.filtered_highlights = highlights;
+        self.update_list_items();
+    }
+    /// Records a successful launch, and the query it was chosen for, in the history and closes
+    /// the launcher.
+    fn finish_launch(&mut self, entry: &Entry) -> ! {
+        self.history.record(entry.id());
+        if !self.query.trim().is_empty() {
+            self.history.record_query(&self.query, entry.id());
+        }
+        if let Err(err) = self.history.save() {
+            log::warn!("Failed to save launch history: {}", err);
+        }
+        std::process::exit(0)
+    }
+    /// Removes the selected entry from the launch history so it no longer gets a ranking bonus.
+    fn forget_selected_entry(&mut self) {
+        let Some(id) = self.get_selected_entry().map(|entry| entry.id().to_string()) else {
+            return;
+        };
+        if !self.history.forget(&id) {
+            return;
+        }
+        if let Err(err) = self.history.save() {
+            log::warn!("Failed to save launch history: {}", err);
+        }
         self.update_filtered_entries();
-        self.find_icons();
+        let index = self.selection_index.min(self.list_items.len().saturating_sub(1));
+        self.set_selection_index(index);
     }
-    fn update_filtered_entries(&mut self) {
-        let matcher = SkimMatcherV2::default();
-        let mut vec = self.entries.iter().flat_map(|entry| {
-            if self.query.is_empty() {
-                Some((0, entry))
-            } else {
-                let title_match = matcher.fuzzy_match(entry.title().as_str(), &self.query);
-                let generic_name_match = entry.generic_name().and_then(|gn| matcher.fuzzy_match(gn.as_str(), &self.query));
-                let score = match (title_match, generic_name_match) {
-                    (Some(ts), Some(gs)) => Some(ts.max(gs)),
-                    (Some(ts), None) => Some(ts),
-                    (None, Some(gs)) => Some(gs),
-                    (None, None) => None,
-                };
-                score.map(|score| {
-                    (score, entry)
-                })
+    fn update_list_items(&mut self) {
+        if !self.query.is_empty() {
+            self.list_items = (0..self.suggestions_start).map(ListItem::Entry).collect();
+            if self.suggestions_start < self.filtered_entries.len() {
+                self.list_items.push(ListItem::Suggestions);
+                self.list_items.extend((self.suggestions_start..self.filtered_entries.len()).map(ListItem::Entry));
             }
-        }).collect::<Vec<_>>();
-        vec.sort_by(|a,b|a.1.title().as_str().cmp(b.1.title().as_str()));
-        vec.sort_by(|a, b| b.0.cmp(&a.0));
-        self.filtered_entries = vec.iter().map(|(_, entry)| (*entry).clone()).collect();
+            return;
+        }
+
+        // With an empty query the applications are browsed by main category. Desktop actions
+        // are only reachable through search so that the sections stay readable.
+        let mut sections: Vec<(&'static str, Vec<usize>)> = Vec::new();
+        for (index, entry) in self.filtered_entries.iter().enumerate() {
+            if entry.parent_id().is_some() {
+                continue;
+            }
+            let category = categories::main_category(entry);
+            match sections.iter_mut().find(|(c, _)| *c == category) {
+                Some((_, indices)) => indices.push(index),
+                None => sections.push((category, vec![index])),
+            }
+        }
+        sections.sort_by_key(|(category, _)| categories::sort_index(category));
+
+        let mut list_items = Vec::new();
+        for (category, indices) in sections {
+            let collapsed = self.collapsed_categories.contains(category);
+            list_items.push(ListItem::Header { category, count: indices.len(), collapsed });
+            if !collapsed {
+                list_items.extend(indices.into_iter().map(ListItem::Entry));
+            }
+        }
+        self.list_items = list_items;
+    }
+    fn toggle_category(&mut self, category: &'static str) {
+        if !self.collapsed_categories.remove(category) {
+            self.collapsed_categories.insert(category);
+        }
+        self.set_selection_index(self.selection_index);
     }
     fn set_query(&mut self, query: String) {
         self.query = query;
-        self.set_selection_index(0);
+        self.launch_error = None;
         self.update_filtered_entries();
+        self.set_selection_index(0);
+    }
+    fn is_selectable(&self, index: usize) -> bool {
+        matches!(self.list_items.get(index), Some(ListItem::Entry(_)))
+    }
+    /// Finds the closest entry row to `index`, searching in the direction of movement first
+    /// so that keyboard navigation skips over section headers.
+    fn nearest_selectable_index(&self, index: usize, forward: bool) -> usize {
+        let after = (index..self.list_items.len()).find(|&i| self.is_selectable(i));
+        let before = (0..=index).rev().find(|&i| self.is_selectable(i));
+        let (first, second) = if forward { (after, before) } else { (before, after) };
+        first.or(second).unwrap_or(0)
     }
     fn set_selection_index(&mut self, index: usize) {
-        self.selection_index = index;
-        if self.selection_index >= self.filtered_entries.len() {
-            self.selection_index = self.filtered_entries.len().saturating_sub(1);
-        }
+        let forward = index >= self.selection_index;
         self.update_filtered_entries();
-        self.scroll_handle.scroll_to_item(index, ScrollStrategy::Top);
+        let index = index.min(self.list_items.len().saturating_sub(1));
+        self.selection_index = self.nearest_selectable_index(index, forward);
+
+        // Keep the section header of the first entry in view.
+        let scroll_index = match self.selection_index.checked_sub(1) {
+            Some(previous) if !self.is_selectable(previous) => previous,
+            _ => self.selection_index,
+        };
+        self.scroll_handle.scroll_to_item(scroll_index, ScrollStrategy::Top);
     }
     fn add_selection_index(&mut self, delta: isize) {
         if delta < 0 {
-            self.set_selection_index(self.selection_index.saturating_sub(delta.abs() as usize));
+            self.set_selection_index(self.selection_index.saturating_sub(delta.unsigned_abs()));
         } else {
             self.set_selection_index(self.selection_index.saturating_add(delta as usize));
         }
         self.update_filtered_entries();
     }
     fn get_selected_entry(&self) -> Option<&Entry> {
-        self.filtered_entries.get(self.selection_index)
+        match self.list_items.get(self.selection_index) {
+            Some(ListItem::Entry(index)) => self.filtered_entries.get(*index),
+            _ => None,
+        }
     }
-    fn launch_entry(&mut self, entry: Entry) {
+    fn launch_entry(&mut self, entry: Entry, cx: &mut gpui::App) {
         if entry.user_confirm() {
             self.confirming_entry = Some(entry);
         }
         else {
-            self.execute_entry(entry);
+            self.execute_entry(entry, cx);
         }
     }
-    fn execute_entry(&self, entry: Entry) {
+    fn report_launch_error(&mut self, message: String) {
+        log::error!("{}", message);
+        self.launch_error = Some(message);
+        self.confirming_entry = None;
+    }
+    fn execute_entry(&mut self, entry: Entry, cx: &mut gpui::App) {
+        if self.launching {
+            return;
+        }
         println!("Launching entry: {:?}", entry);
-        let command = entry.launch_command();
 
-        if let Some(command) = command {
-            let Ok(args) = shell_words::split(&command) else {
-                return;
-            };
-            let args: Vec<String> = args.into_iter().flat_map(|part|{
-                if part == "%%" {
-                    Some("%".to_string())
-                } else if part.starts_with("%") {
-                    None
-                } else {
-                    Some(part)
-                }
-            }).collect();
-            
+        if let EntryKind::Link { url } = entry.kind() {
+            // The launcher stays open until the URL has been handed over, so that a failure
+            // can still be shown.
+            let url = url.clone();
+            let opening = cx.background_executor().spawn({
+                let url = url.clone();
+                async move { open_url(&url) }
+            });
+            self.launching = true;
+            cx.spawn(async move |cx| {
+                let result = opening.await;
+                cx.update_global(|state: &mut State, _cx| {
+                    state.launching = false;
+                    match result {
+                        Ok(()) => state.finish_launch(&entry),
+                        Err(err) => state.report_launch_error(format!("Failed to open {}: {}", url, err)),
+                    }
+                }).ok();
+            }).detach();
+            return;
+        }
+
+        // One startup sequence covers the D-Bus attempt and the Exec fallback.
+        let startup_token = startup::begin(&entry);
+
+        if entry.is_dbus_activatable() {
+            let targets = self.launch_targets.clone();
+            // The call blocks until the application has been started and replies, so it runs
+            // on the background executor to keep the window responsive.
+            let activation = cx.background_executor().spawn({
+                let entry = entry.clone();
+                let startup_token = startup_token.clone();
+                async move { dbus::activate(&entry, &targets, startup_token.as_ref()) }
+            });
+            self.launching = true;
+            cx.spawn(async move |cx| {
+                let result = activation.await;
+                cx.update_global(|state: &mut State, _cx| {
+                    state.launching = false;
+                    match result {
+                        Ok(()) => state.finish_launch(&entry),
+                        // DBusActivatable entries do not need an Exec key to fall back to.
+                        Err(err) if entry.launch_command().is_none() => {
+                            if let Some(token) = &startup_token {
+                                startup::cancel(token);
+                            }
+                            state.report_launch_error(format!("Failed to activate {} over D-Bus: {}", entry.title(), err));
+                        }
+                        Err(err) => {
+                            log::warn!("D-Bus activation of {:?} failed, falling back to Exec: {}", entry.id(), err);
+                            state.execute_command(entry, startup_token);
+                        }
+                    }
+                }).ok();
+            }).detach();
+            return;
+        }
+
+        self.execute_command(entry, startup_token);
+    }
+    fn execute_command(&mut self, entry: Entry, startup_token: Option<startup::StartupToken>) {
+        if !self.spawn_command(&entry, startup_token.as_ref()) {
+            if let Some(token) = &startup_token {
+                startup::cancel(token);
+            }
+            return;
+        }
+        self.finish_launch(&entry);
+    }
+    /// Starts the processes of the entry's Exec value, returning whether any of them started.
+    fn spawn_command(&mut self, entry: &Entry, startup_token: Option<&startup::StartupToken>) -> bool {
+        let Some(command) = entry.launch_command() else {
+            return false;
+        };
+
+        let args = match exec::parse_exec(command) {
+            Ok(args) => args,
+            Err(err) => {
+                self.report_launch_error(format!("Invalid Exec value {:?}: {}", command, err));
+                return false;
+            }
+        };
+        let title = entry.title();
+        let context = ExecContext {
+            icon: entry.icon(),
+            name: &title,
+            desktop_file: entry.source_path(),
+            targets: &self.launch_targets,
+        };
+        let commands = match exec::expand_field_codes(&args, &context) {
+            Ok(commands) => commands,
+            Err(err) => {
+                self.report_launch_error(format!("Invalid Exec value {:?}: {}", command, err));
+                return false;
+            }
+        };
+
+        let working_dir = match entry.working_dir() {
+            Some(dir) => Some(dir.to_path_buf()),
+            None => std::env::home_dir(),
+        };
+        if let Some(dir) = &working_dir && !dir.is_dir() {
+            self.report_launch_error(format!(
+                "Cannot launch {}: working directory {} does not exist",
+                entry.title(),
+                dir.display(),
+            ));
+            return false;
+        }
+
+        let mut launched = false;
+        for args in commands {
             println!("Launching command: {:?}", args);
             
             #[cfg(target_family = "unix")]
             {
                 use std::process::Command;
 
-                if entry.is_terminal() {
-                    Command::new("xdg-terminal")
-                        .args(&args)
-                        .spawn()
-                        .expect("Failed to launch command");
+                let mut command = if entry.is_terminal() {
+                    let mut command = Command::new("xdg-terminal");
+                    command.args(&args);
+                    command
                 }
                 else {
-                    Command::new(&args[0])
-                        .args(&args[1..])
-                        .spawn()
-                        .expect("Failed to launch command");
+                    let mut command = Command::new(&args[0]);
+                    command.args(&args[1..]);
+                    command
+                };
+                if let Some(dir) = &working_dir {
+                    command.current_dir(dir);
+                }
+                for var in startup::STARTUP_ENV_VARS {
+                    command.env_remove(var);
+                }
+                if let Some(token) = startup_token {
+                    command.envs(token.env());
+                }
+                match command.spawn() {
+                    Ok(_) => launched = true,
+                    Err(err) => self.report_launch_error(format!("Failed to launch {:?}: {}", args[0], err)),
                 }
             }
-            
-            std::process::exit(0);
         }
+
+        launched
     }
 }
 
@@ -238,6 +583,16 @@ impl Render for App {
                     .appearance(false)
                 )
             )
+            .when_some(state.query_error.clone().or(state.launch_error.clone()), |e, error| {
+                e.child(
+                    div()
+                    .px(px(8.))
+                    .pb(px(4.))
+                    .text_size(px(13.))
+                    .text_color(rgb(0xf38ba8))
+                    .child(error)
+                )
+            })
             .child(
                 div()
                 .overflow_hidden()
@@ -264,19 +619,68 @@ impl Render for App {
                         v_virtual_list(
                             cx.entity().clone(),
                             "entry-list",
-                            Rc::new(vec![size(px(9999.), px(48.)); state.filtered_entries.len()]),
+                            Rc::new(state.list_items.iter().map(|item| match item {
+                                ListItem::Header { .. } | ListItem::Suggestions => size(px(9999.), px(28.)),
+                                ListItem::Entry(_) => size(px(9999.), px(48.)),
+                            }).collect()),
                             |_view, visible_range, _window, cx| {
                                 visible_range
                                     .map(|ix| {
                                         let state = cx.global::<State>();
                                         let is_selected = state.selection_index == ix;
-                                        let entry = state.filtered_entries.get(ix);
+                                        let entry = match state.list_items.get(ix) {
+                                            Some(ListItem::Header { category, count, collapsed }) => {
+                                                let category = *category;
+                                                return div()
+                                                    .flex()
+                                                    .flex_row()
+                                                    .items_end()
+                                                    .gap(px(4.))
+                                                    .h(px(28.))
+                                                    .w_full()
+                                                    .px(px(4.))
+                                                    .pb(px(2.))
+                                                    .text_size(px(13.))
+                                                    .text_color(rgb(0xa6adc8))
+                                                    .cursor_pointer()
+                                                    .child(if *collapsed { "▸" } else { "▾" })
+                                                    .child(categories::display_name(category).to_string())
+                                                    .child(
+                                                        div()
+                                                        .text_color(rgb(0x7f849c))
+                                                        .child(format!("{}", count))
+                                                    )
+                                                    .on_mouse_down(MouseButton::Left, move |_event, _window, cx| {
+                                                        State::update_global(cx, |state, _cx| {
+                                                            state.toggle_category(category);
+                                                        });
+                                                    });
+                                            }
+                                            Some(ListItem::Suggestions) => {
+                                                return div()
+                                                    .flex()
+                                                    .items_end()
+                                                    .h(px(28.))
+                                                    .w_full()
+                                                    .px(px(4.))
+                                                    .pb(px(2.))
+                                                    .text_size(px(13.))
+                                                    .text_color(rgb(0xa6adc8))
+                                                    .child("Did you mean");
+                                            }
+                                            Some(ListItem::Entry(index)) => state.filtered_entries.get(*index),
+                                            None => None,
+                                        };
 
                                         let Some(entry) = entry else {
                                             return div();
                                         };
+                                        let highlights = match state.list_items.get(ix) {
+                                            Some(ListItem::Entry(index)) => state.filtered_highlights.get(*index),
+                                            _ => None,
+                                        }.cloned().unwrap_or_default();
 
-                                        let img_src = entry.icon().map(|icon|state.icon_map.get(icon)).flatten();
+                                        let img_src = entry.icon().and_then(|icon|state.icon_map.get(icon));
 
                                         let img_el = match img_src {
                                             Some(src) => vec![
@@ -296,13 +700,14 @@ impl Render for App {
                                             .rounded(px(4.))
                                             .w_full()
                                             .overflow_hidden()
-                                            .bg(if is_selected { rgb(0x313244).with_alpha(0.8) } else { rgba(0x0).into() })
+                                            .bg(if is_selected { rgb(0x313244).with_alpha(0.8) } else { rgba(0x0) })
                                             .child(
                                                 div()
                                                 .flex_basis(px(48.))
                                                 .flex_none()
                                                 .h_full()
                                                 .p_1()
+                                                .when(entry.is_desktop_filtered(), |e| e.opacity(0.5))
                                                 .children(img_el)
                                             )
                                             .child(
@@ -310,9 +715,10 @@ impl Render for App {
                                                 .flex_1()
                                                 .w_full()
                                                 .overflow_hidden()
+                                                .when(entry.is_desktop_filtered(), |e| e.opacity(0.5))
                                                 .child(
                                                     div()
-                                                    .child(entry.title().to_string())
+                                                    .child(highlighted_text(entry.title(), &highlights.title))
                                                     .text_size(px(16.))
                                                     .overflow_hidden()
                                                     .w_full()
@@ -320,9 +726,17 @@ impl Render for App {
                                                 )
                                                 .child(
                                                     div()
-                                                    .child(entry.description().unwrap_or_default().to_string())
+                                                    .map(|e| if entry.is_missing_executable() {
+                                                        e.child("Program not found")
+                                                            .text_color(rgb(0xf38ba8))
+                                                    } else if let Some(detail) = &highlights.detail {
+                                                        e.child(highlighted_text(detail.text.clone(), &detail.indices))
+                                                            .text_color(rgb(0xbac2de))
+                                                    } else {
+                                                        e.child(entry.description().unwrap_or_default())
+                                                            .text_color(rgb(0xbac2de))
+                                                    })
                                                     .text_size(px(14.))
-                                                    .text_color(rgb(0xbac2de))
                                                     .overflow_hidden()
                                                     .w_full()
                                                     .text_ellipsis()
@@ -400,11 +814,11 @@ impl Render for App {
                                 .w_full()
                                 .child(Kbd::new(Keystroke::parse("ctrl-enter").unwrap()))
                                 .on_click(|_event, _window, cx| {
-                                    let state = cx.global::<State>();
-                                    let entry = state.confirming_entry.clone();
-                                    if let Some(entry) = entry {
-                                        state.execute_entry(entry);
-                                    }
+                                    State::update_global(cx, |state, cx| {
+                                        if let Some(entry) = state.confirming_entry.clone() {
+                                            state.execute_entry(entry, cx);
+                                        }
+                                    });
                                 })
                             )
                             .child(
@@ -416,7 +830,9 @@ impl Render for App {
                                 .on_click(|_event, window, _cx| {
                                     State::update_global(_cx, |state, _cx| {
                                         state.confirming_entry = None;
-                                        state.input_focus_handle.clone().map(|h| h.focus(window));
+                                        if let Some(h) = state.input_focus_handle.clone() {
+                                            h.focus(window);
+                                        }
                                     });
                                 })
                             )
@@ -436,11 +852,11 @@ impl App {
                 .placeholder("Search")
         );
         let focus_handle = input.focus_handle(cx);
-        State::update_global(cx, |state, cx| {
+        State::update_global(cx, |state, _cx| {
             state.input_focus_handle = Some(focus_handle);
         });
 
-        cx.subscribe_in(&input, window, |view, state, event, window, cx|{
+        cx.subscribe_in(&input, window, |_view, state, event, _window, cx|{
             match event {
                 InputEvent::Change => {
                     let text = state.read(cx).value();
@@ -451,7 +867,7 @@ impl App {
                 InputEvent::PressEnter { secondary: _ } => {
                     cx.update_global(|state: &mut State, cx| {
                         if let Some(entry) = state.get_selected_entry().cloned() {
-                            state.launch_entry(entry);
+                            state.launch_entry(entry, cx);
                         }
                     });
                 }
@@ -469,9 +885,41 @@ impl App {
 
 fn main() {
     env_logger::init();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if matches!(args.first().map(String::as_str), Some("doctor" | "--diagnose")) {
+        let json = args.iter().any(|arg| arg == "--json");
+        if let Err(err) = doctor::run(json) {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
+    if args.first().map(String::as_str) == Some("clear-history") {
+        if let Err(err) = history::run_clear() {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     let app = Application::new();
-    let mut state = State::default();
-    state.refresh_entries();
+    let mut state = State {
+        launch_targets: args,
+        config: Config::load(),
+        ..Default::default()
+    };
+    state.folder = Folder::new(&state.config);
+    state.history = History::load();
+    // Paint straight from the cache when there is one; the watcher validates it in the background.
+    let (desktop_entries, cache_key) = match cache::load() {
+        Some(cache) => {
+            state.set_entries(cache.entries.clone());
+            state.icon_map = cache.icon_map;
+            (cache.entries, Some(cache.key))
+        }
+        None => (state.refresh_entries(), None),
+    };
+    let mut updates = watcher::spawn(state.config.clone(), desktop_entries, state.icon_map.clone(), cache_key);
 
     app.run(move |cx| {
         gpui_component::init(cx);
@@ -504,7 +952,13 @@ fn main() {
             if event.keystroke.key == "pagedown" {
                 State::update_global(cx, |state, _cx| {
                     if state.confirming_entry.is_some() { return; }
-                    state.set_selection_index(state.filtered_entries.len().saturating_sub(1));
+                    state.set_selection_index(state.list_items.len().saturating_sub(1));
+                });
+            }
+            if event.keystroke.key == "delete" && event.keystroke.modifiers.control {
+                State::update_global(cx, |state, _cx| {
+                    if state.confirming_entry.is_some() { return; }
+                    state.forget_selected_entry();
                 });
             }
             if event.keystroke.key == "escape" {
@@ -512,26 +966,35 @@ fn main() {
                 if state.confirming_entry.is_some() {
                     State::update_global(cx, |state, _cx| {
                         state.confirming_entry = None;
-                        state.input_focus_handle.clone().map(|h| h.focus(_window));
+                        if let Some(h) = state.input_focus_handle.clone() {
+                            h.focus(_window);
+                        }
                     });
                     return;
                 }
                 cx.quit();
             }
-            if event.keystroke.key == "enter" {
-                if event.keystroke.modifiers.control {
-                    let state = cx.global::<State>();
-                    let entry = state.confirming_entry.clone();
-                    if let Some(entry) = entry {
-                        State::update_global(cx, |state, _cx| {
-                            state.execute_entry(entry);
-                        });
-                        return;
-                    }
+            if event.keystroke.key == "enter" && event.keystroke.modifiers.control {
+                let state = cx.global::<State>();
+                let entry = state.confirming_entry.clone();
+                if let Some(entry) = entry {
+                    State::update_global(cx, |state, cx| {
+                        state.execute_entry(entry, cx);
+                    });
                 }
             }
         }).detach();
 
+        cx.spawn(async move |cx| {
+            while let Some(updates) = updates.next().await {
+                cx.update_global(|state: &mut State, _cx| {
+                    state.apply_updates(updates);
+                })?;
+            }
+            Ok::<_, anyhow::Error>(())
+        })
+        .detach();
+
         cx.spawn(async move |cx| {
             let window = cx.open_window(WindowOptions {
                 window_decorations: Some(WindowDecorations::Client),
@@ -0,0 +1,451 @@
+use fuzzy_matcher::FuzzyMatcher;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use regex::{Regex, RegexBuilder};
+
+/// Score awarded per matched query character, in line with the skim matcher's scores.
+const CHAR_SCORE: i64 = 16;
+
+/// A successful match of the query against a value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match {
+    pub score: i64,
+    /// Character (not byte) indices into the value that matched the query.
+    pub indices: Vec<usize>,
+}
+
+/// A matching algorithm, constructed for a single query.
+pub trait Matcher {
+    fn find(&self, value: &str) -> Option<Match>;
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MatcherKind {
+    #[default]
+    Skim,
+    Prefix,
+    Substring,
+    Initials,
+    Exact,
+    Regex,
+}
+
+impl MatcherKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "skim" | "fuzzy" => Some(MatcherKind::Skim),
+            "prefix" => Some(MatcherKind::Prefix),
+            "substring" => Some(MatcherKind::Substring),
+            "initials" | "acronym" => Some(MatcherKind::Initials),
+            "exact" => Some(MatcherKind::Exact),
+            "regex" => Some(MatcherKind::Regex),
+            _ => None,
+        }
+    }
+
+    fn from_sigil(sigil: char) -> Option<Self> {
+        match sigil {
+            '~' => Some(MatcherKind::Skim),
+            '^' => Some(MatcherKind::Prefix),
+            '\'' => Some(MatcherKind::Substring),
+            '.' => Some(MatcherKind::Initials),
+            '=' => Some(MatcherKind::Exact),
+            '/' => Some(MatcherKind::Regex),
+            _ => None,
+        }
+    }
+}
+
+/// Splits an algorithm-selecting sigil off the query, e.g. `^fire` for a prefix match or
+/// `/^gimp?` for a regex. Without a sigil the configured default is used.
+pub fn split_query(query: &str, default: MatcherKind) -> (MatcherKind, &str) {
+    let mut chars = query.chars();
+    match chars.next().and_then(MatcherKind::from_sigil) {
+        Some(kind) => (kind, chars.as_str()),
+        None => (default, query),
+    }
+}
+
+/// Splits the query into tokens that are matched independently. Regex and exact queries are
+/// kept whole, since their whitespace is part of the pattern.
+pub fn split_tokens(kind: MatcherKind, query: &str) -> Vec<&str> {
+    match kind {
+        MatcherKind::Regex | MatcherKind::Exact if !query.trim().is_empty() => vec![query.trim()],
+        _ => query.split_whitespace().collect(),
+    }
+}
+
+/// Builds the matcher for `query`. Only fails for an invalid regex.
+pub fn new_matcher(kind: MatcherKind, query: &str) -> anyhow::Result<Box<dyn Matcher>> {
+    let query: Vec<char> = query.chars().collect();
+    Ok(match kind {
+        MatcherKind::Skim => Box::new(SkimMatcher {
+            matcher: SkimMatcherV2::default(),
+            query: query.into_iter().collect(),
+        }),
+        MatcherKind::Prefix => Box::new(PrefixMatcher { query }),
+        MatcherKind::Substring => Box::new(SubstringMatcher { query }),
+        MatcherKind::Initials => Box::new(InitialsMatcher { query }),
+        MatcherKind::Exact => Box::new(ExactMatcher { query }),
+        MatcherKind::Regex => Box::new(RegexMatcher {
+            regex: RegexBuilder::new(&query.into_iter().collect::<String>())
+                .case_insensitive(true)
+                .build()
+                .map_err(|err| {
+                    // Syntax errors span several lines with the pattern drawn above the message.
+                    let message = err.to_string();
+                    let reason = message.lines().last().unwrap_or_default().trim_start_matches("error: ").to_string();
+                    anyhow::anyhow!("Invalid regular expression: {}", reason)
+                })?,
+        }),
+    })
+}
+
+fn chars_eq(a: char, b: char) -> bool {
+    a == b || a.to_lowercase().eq(b.to_lowercase())
+}
+
+fn starts_with(value: &[char], query: &[char]) -> bool {
+    value.len() >= query.len() && value.iter().zip(query).all(|(&a, &b)| chars_eq(a, b))
+}
+
+/// Whether a word starts at `index`: after a separator or at a lower-to-upper case change.
+fn is_word_start(value: &[char], index: usize) -> bool {
+    let Some(&c) = value.get(index) else {
+        return false;
+    };
+    if !c.is_alphanumeric() {
+        return false;
+    }
+    match index.checked_sub(1).map(|i| value[i]) {
+        None => true,
+        Some(prev) => !prev.is_alphanumeric() || (prev.is_lowercase() && c.is_uppercase()),
+    }
+}
+
+struct SkimMatcher {
+    matcher: SkimMatcherV2,
+    query: String,
+}
+
+impl Matcher for SkimMatcher {
+    fn find(&self, value: &str) -> Option<Match> {
+        self.matcher.fuzzy_indices(value, &self.query)
+            .map(|(score, indices)| Match { score, indices })
+    }
+}
+
+struct PrefixMatcher {
+    query: Vec<char>,
+}
+
+impl Matcher for PrefixMatcher {
+    fn find(&self, value: &str) -> Option<Match> {
+        let value: Vec<char> = value.chars().collect();
+        if !starts_with(&value, &self.query) {
+            return None;
+        }
+        // Prefer values that the query covers more of.
+        let coverage = CHAR_SCORE * self.query.len() as i64 / value.len().max(1) as i64;
+        Some(Match {
+            score: CHAR_SCORE * self.query.len() as i64 + 2 * CHAR_SCORE + coverage,
+            indices: (0..self.query.len()).collect(),
+        })
+    }
+}
+
+struct SubstringMatcher {
+    query: Vec<char>,
+}
+
+impl Matcher for SubstringMatcher {
+    fn find(&self, value: &str) -> Option<Match> {
+        let value: Vec<char> = value.chars().collect();
+        let start = (0..=value.len().checked_sub(self.query.len())?)
+            .find(|&start| starts_with(&value[start..], &self.query))?;
+        // Matches at a word boundary rank above those in the middle of a word, and earlier
+        // matches above later ones.
+        let boundary_bonus = if is_word_start(&value, start) { CHAR_SCORE } else { 0 };
+        let position_penalty = start.min(CHAR_SCORE as usize) as i64;
+        Some(Match {
+            score: CHAR_SCORE * self.query.len() as i64 + boundary_bonus - position_penalty,
+            indices: (start..start + self.query.len()).collect(),
+        })
+    }
+}
+
+/// Matches the query against the first letters of the value's words in order, so `vsc` finds
+/// "Visual Studio Code" and `lo` finds "LibreOffice".
+struct InitialsMatcher {
+    query: Vec<char>,
+}
+
+impl Matcher for InitialsMatcher {
+    fn find(&self, value: &str) -> Option<Match> {
+        let value: Vec<char> = value.chars().collect();
+        let initials: Vec<usize> = (0..value.len()).filter(|&index| is_word_start(&value, index)).collect();
+
+        let mut indices = Vec::with_capacity(self.query.len());
+        let mut skipped = 0;
+        let mut remaining = initials.iter();
+        for &q in &self.query {
+            loop {
+                let &index = remaining.next()?;
+                if chars_eq(value[index], q) {
+                    indices.push(index);
+                    break;
+                }
+                skipped += 1;
+            }
+        }
+        let complete_bonus = if indices.len() == initials.len() { CHAR_SCORE } else { 0 };
+        Some(Match {
+            score: CHAR_SCORE * self.query.len() as i64 + complete_bonus - CHAR_SCORE / 2 * skipped,
+            indices,
+        })
+    }
+}
+
+struct ExactMatcher {
+    query: Vec<char>,
+}
+
+impl Matcher for ExactMatcher {
+    fn find(&self, value: &str) -> Option<Match> {
+        let value: Vec<char> = value.chars().collect();
+        if value.len() != self.query.len() || !starts_with(&value, &self.query) {
+            return None;
+        }
+        Some(Match {
+            score: CHAR_SCORE * self.query.len() as i64 + 4 * CHAR_SCORE,
+            indices: (0..value.len()).collect(),
+        })
+    }
+}
+
+struct RegexMatcher {
+    regex: Regex,
+}
+
+impl Matcher for RegexMatcher {
+    fn find(&self, value: &str) -> Option<Match> {
+        let found = self.regex.find(value)?;
+        let start = value[..found.start()].chars().count();
+        let len = found.as_str().chars().count();
+        Some(Match {
+            score: CHAR_SCORE * len.max(1) as i64 - start.min(CHAR_SCORE as usize) as i64,
+            indices: (start..start + len).collect(),
+        })
+    }
+}
+
+/// Optimal string alignment distance: Levenshtein distance where swapping two adjacent
+/// characters also counts as a single edit.
+fn damerau_levenshtein(a: &[char], b: &[char]) -> usize {
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in rows[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if chars_eq(a[i - 1], b[j - 1]) { 0 } else { 1 };
+            let mut distance = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && chars_eq(a[i - 1], b[j - 2]) && chars_eq(a[i - 2], b[j - 1]) {
+                distance = distance.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = distance;
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+/// Matches a query token against whole words of the value, or their beginnings, allowing a few
+/// typos. Used as a second pass when the configured matcher finds too little.
+struct TypoMatcher {
+    query: Vec<char>,
+    max_distance: usize,
+}
+
+impl Matcher for TypoMatcher {
+    fn find(&self, value: &str) -> Option<Match> {
+        let value: Vec<char> = value.chars().collect();
+        let mut best: Option<(usize, Vec<usize>)> = None;
+        for start in (0..value.len()).filter(|&index| is_word_start(&value, index)) {
+            let end = (start..value.len()).find(|&i| !value[i].is_alphanumeric()).unwrap_or(value.len());
+            let word = &value[start..end];
+            // Compare against the whole word and against a prefix as long as the query, so that
+            // a partially typed word can still be corrected.
+            let prefix = &word[..word.len().min(self.query.len())];
+            let (distance, len) = [(damerau_levenshtein(&self.query, word), word.len()), (damerau_levenshtein(&self.query, prefix), prefix.len())]
+                .into_iter()
+                .min()?;
+            if distance <= self.max_distance && best.as_ref().is_none_or(|(best, _)| distance < *best) {
+                best = Some((distance, (start..start + len).collect()));
+            }
+        }
+        let (distance, indices) = best?;
+        Some(Match {
+            score: CHAR_SCORE * (self.query.len() as i64 - 2 * distance as i64),
+            indices,
+        })
+    }
+}
+
+struct FallbackMatcher {
+    primary: Box<dyn Matcher>,
+    typo: TypoMatcher,
+}
+
+impl Matcher for FallbackMatcher {
+    fn find(&self, value: &str) -> Option<Match> {
+        self.primary.find(value).or_else(|| self.typo.find(value))
+    }
+}
+
+/// Wraps the matcher for a query token so that values it does not match can still be found
+/// with typos. Tokens too short for typo correction to be meaningful keep the plain matcher.
+pub fn with_typo_fallback(primary: Box<dyn Matcher>, query: &str) -> Box<dyn Matcher> {
+    let query: Vec<char> = query.chars().collect();
+    let max_distance = match query.len() {
+        0..=2 => return primary,
+        3..=5 => 1,
+        _ => 2,
+    };
+    Box::new(FallbackMatcher {
+        primary,
+        typo: TypoMatcher { query, max_distance },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find(kind: MatcherKind, query: &str, value: &str) -> Option<Match> {
+        new_matcher(kind, query).unwrap().find(value)
+    }
+
+    fn indices(kind: MatcherKind, query: &str, value: &str) -> Option<Vec<usize>> {
+        find(kind, query, value).map(|found| found.indices)
+    }
+
+    fn score(kind: MatcherKind, query: &str, value: &str) -> i64 {
+        find(kind, query, value).unwrap().score
+    }
+
+    #[test]
+    fn indices_are_character_indices() {
+        let value = "Überprüfung";
+        assert_eq!(indices(MatcherKind::Skim, "prü", value), Some(vec![4, 5, 6]));
+        assert_eq!(indices(MatcherKind::Prefix, "überp", value), Some(vec![0, 1, 2, 3, 4]));
+        assert_eq!(indices(MatcherKind::Substring, "PRÜ", value), Some(vec![4, 5, 6]));
+        assert_eq!(indices(MatcherKind::Initials, "üf", "Über Füße"), Some(vec![0, 5]));
+        assert_eq!(indices(MatcherKind::Exact, "ÜBERPRÜFUNG", value), Some((0..11).collect()));
+        assert_eq!(indices(MatcherKind::Regex, "pr.f", value), Some(vec![4, 5, 6, 7]));
+    }
+
+    #[test]
+    fn initials_match_word_starts() {
+        assert_eq!(indices(MatcherKind::Initials, "vsc", "Visual Studio Code"), Some(vec![0, 7, 14]));
+        assert_eq!(indices(MatcherKind::Initials, "lo", "LibreOffice"), Some(vec![0, 5]));
+        assert_eq!(indices(MatcherKind::Initials, "ib", "GNU Image Manipulation Program"), None);
+        assert!(score(MatcherKind::Initials, "vsc", "Visual Studio Code") > score(MatcherKind::Initials, "vsc", "Visual Studio Code Insiders"));
+    }
+
+    #[test]
+    fn prefix_and_exact_scoring() {
+        assert_eq!(find(MatcherKind::Prefix, "fox", "Firefox"), None);
+        assert_eq!(find(MatcherKind::Exact, "fire", "Firefox"), None);
+        // A prefix covering more of the value ranks higher.
+        assert!(score(MatcherKind::Prefix, "fire", "Firefox") > score(MatcherKind::Prefix, "fire", "Firefox Developer Edition"));
+        // An exact match beats the same query as a prefix.
+        assert!(score(MatcherKind::Exact, "firefox", "Firefox") > score(MatcherKind::Prefix, "firefox", "Firefox"));
+    }
+
+    #[test]
+    fn substring_prefers_word_starts() {
+        assert_eq!(indices(MatcherKind::Substring, "fox", "Firefox"), Some(vec![4, 5, 6]));
+        assert!(score(MatcherKind::Substring, "fox", "Fox Browser") > score(MatcherKind::Substring, "fox", "Firefox"));
+        assert!(score(MatcherKind::Substring, "fox", "Big Fox") > score(MatcherKind::Substring, "fox", "Firefox"));
+    }
+
+    #[test]
+    fn invalid_regex_reports_the_reason() {
+        let err = new_matcher(MatcherKind::Regex, "gimp(").err().unwrap();
+        assert_eq!(err.to_string(), "Invalid regular expression: unclosed group");
+    }
+
+    #[test]
+    fn sigils_select_the_matcher() {
+        assert_eq!(split_query("~fire", MatcherKind::Prefix), (MatcherKind::Skim, "fire"));
+        assert_eq!(split_query("^fire", MatcherKind::Skim), (MatcherKind::Prefix, "fire"));
+        assert_eq!(split_query("'fox", MatcherKind::Skim), (MatcherKind::Substring, "fox"));
+        assert_eq!(split_query(".vsc", MatcherKind::Skim), (MatcherKind::Initials, "vsc"));
+        assert_eq!(split_query("=Firefox", MatcherKind::Skim), (MatcherKind::Exact, "Firefox"));
+        assert_eq!(split_query("/^gimp?", MatcherKind::Skim), (MatcherKind::Regex, "^gimp?"));
+        assert_eq!(split_query("fire", MatcherKind::Substring), (MatcherKind::Substring, "fire"));
+        assert_eq!(split_query("", MatcherKind::Skim), (MatcherKind::Skim, ""));
+    }
+
+    #[test]
+    fn regex_and_exact_queries_are_not_split() {
+        assert_eq!(split_tokens(MatcherKind::Skim, " text  editor "), vec!["text", "editor"]);
+        assert_eq!(split_tokens(MatcherKind::Regex, " text .*editor "), vec!["text .*editor"]);
+        assert_eq!(split_tokens(MatcherKind::Exact, "Text Editor"), vec!["Text Editor"]);
+        assert!(split_tokens(MatcherKind::Exact, "  ").is_empty());
+    }
+
+    fn distance(a: &str, b: &str) -> usize {
+        damerau_levenshtein(&a.chars().collect::<Vec<_>>(), &b.chars().collect::<Vec<_>>())
+    }
+
+    fn typo(query: &str, max_distance: usize) -> TypoMatcher {
+        TypoMatcher { query: query.chars().collect(), max_distance }
+    }
+
+    #[test]
+    fn damerau_levenshtein_counts_transpositions_once() {
+        assert_eq!(distance("firefox", "firefox"), 0);
+        assert_eq!(distance("GIMP", "gimp"), 0);
+        assert_eq!(distance("fierfox", "firefox"), 1);
+        assert_eq!(distance("firefx", "firefox"), 1);
+        assert_eq!(distance("firafox", "firefox"), 1);
+        assert_eq!(distance("kitten", "sitting"), 3);
+        assert_eq!(distance("", "vim"), 3);
+        assert_eq!(distance("vim", ""), 3);
+        // Optimal string alignment does not edit a transposed pair again.
+        assert_eq!(distance("ca", "abc"), 3);
+    }
+
+    #[test]
+    fn typos_match_whole_words() {
+        assert_eq!(typo("fierfox", 2).find("Firefox").map(|found| found.indices), Some((0..7).collect()));
+        assert_eq!(typo("wrtier", 2).find("LibreOffice Writer").map(|found| found.indices), Some((12..18).collect()));
+        assert_eq!(typo("chromium", 1).find("Firefox"), None);
+        // Fewer typos score higher.
+        assert!(typo("firefx", 2).find("Firefox").unwrap().score > typo("fierfx", 2).find("Firefox").unwrap().score);
+    }
+
+    #[test]
+    fn typos_are_corrected_in_partially_typed_words() {
+        assert_eq!(typo("fierf", 1).find("Firefox").map(|found| found.indices), Some(vec![0, 1, 2, 3, 4]));
+        assert_eq!(typo("thunderbrid", 2).find("Mozilla Thunderbird Mail").map(|found| found.indices), Some((8..19).collect()));
+        assert_eq!(typo("fierf", 1).find("Chromium"), None);
+    }
+
+    #[test]
+    fn short_tokens_get_no_typo_fallback() {
+        let fallback = |query: &str| with_typo_fallback(new_matcher(MatcherKind::Prefix, query).unwrap(), query);
+        assert_eq!(fallback("fx").find("Fox"), None);
+        assert_eq!(fallback("fpx").find("Fox").map(|found| found.indices), Some(vec![0, 1, 2]));
+        // The primary matcher wins when it matches.
+        assert_eq!(fallback("fir").find("Firefox"), new_matcher(MatcherKind::Prefix, "fir").unwrap().find("Firefox"));
+        // Tokens of up to five characters allow one typo, longer ones two.
+        assert_eq!(fallback("fxrxf").find("Firefox"), None);
+        assert!(fallback("fxrxfox").find("Firefox").is_some());
+    }
+}
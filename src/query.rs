@@ -0,0 +1,287 @@
+use std::path::PathBuf;
+use std::sync::LazyLock;
+
+use crate::categories;
+use crate::data;
+use crate::entry::{Entry, EntryKind};
+use crate::matcher::{self, MatcherKind};
+
+static DATA_HOME: LazyLock<Option<PathBuf>> = LazyLock::new(data::get_data_home);
+
+/// Where an entry was installed from, for `src:` filters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Flatpak,
+    Snap,
+    Nix,
+    User,
+    System,
+    /// The launcher's own entries, such as logout and shutdown.
+    Builtin,
+}
+
+impl Source {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "flatpak" => Some(Source::Flatpak),
+            "snap" => Some(Source::Snap),
+            "nix" => Some(Source::Nix),
+            "user" | "local" => Some(Source::User),
+            "system" => Some(Source::System),
+            "builtin" => Some(Source::Builtin),
+            _ => None,
+        }
+    }
+
+    /// Guesses the source from the location of the entry's desktop file.
+    pub fn of(entry: &Entry) -> Self {
+        let Some(path) = entry.source_path() else {
+            return Source::Builtin;
+        };
+        let path_str = path.to_string_lossy();
+        if path_str.contains("/flatpak/exports/") {
+            Source::Flatpak
+        } else if path_str.contains("/snapd/") || path_str.starts_with("/snap/") {
+            Source::Snap
+        } else if path_str.starts_with("/nix/")
+            || path_str.contains("/.nix-profile/")
+            || path_str.starts_with("/run/current-system/")
+            || path_str.starts_with("/etc/profiles/")
+        {
+            Source::Nix
+        } else if DATA_HOME.as_ref().is_some_and(|home| path.starts_with(home)) {
+            Source::User
+        } else {
+            Source::System
+        }
+    }
+}
+
+/// The `kind:` values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Application,
+    Action,
+    Link,
+}
+
+impl Kind {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "app" | "application" => Some(Kind::Application),
+            "action" => Some(Kind::Action),
+            "link" => Some(Kind::Link),
+            _ => None,
+        }
+    }
+
+    fn of(entry: &Entry) -> Self {
+        match entry.kind() {
+            EntryKind::Link { .. } => Kind::Link,
+            EntryKind::Application if entry.parent_id().is_some() => Kind::Action,
+            EntryKind::Application => Kind::Application,
+        }
+    }
+}
+
+/// The keys that start a filter; any other `word:` is searched for as it is.
+const FILTER_KEYS: &[&str] = &["cat", "category", "term", "terminal", "src", "source", "kind"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    /// Matches a category, or the start of one, by its spec name or its section name.
+    Category(String),
+    Terminal(bool),
+    Source(Source),
+    Kind(Kind),
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value {
+        "yes" | "true" | "1" => Some(true),
+        "no" | "false" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+impl Filter {
+    fn parse(key: &str, value: &str) -> anyhow::Result<Self> {
+        let value = value.to_lowercase();
+        match key {
+            "cat" | "category" => Ok(Filter::Category(value)),
+            "term" | "terminal" => parse_bool(&value)
+                .map(Filter::Terminal)
+                .ok_or_else(|| anyhow::anyhow!("Expected yes or no after {}:, found {:?}", key, value)),
+            "src" | "source" => Source::from_name(&value)
+                .map(Filter::Source)
+                .ok_or_else(|| anyhow::anyhow!(
+                    "Unknown source {:?}; expected flatpak, snap, nix, user, system or builtin",
+                    value,
+                )),
+            "kind" => Kind::from_name(&value)
+                .map(Filter::Kind)
+                .ok_or_else(|| anyhow::anyhow!("Unknown kind {:?}; expected app, action or link", value)),
+            _ => anyhow::bail!("Unknown filter {}:; expected cat:, term:, src: or kind:", key),
+        }
+    }
+
+    pub fn matches(&self, entry: &Entry) -> bool {
+        match self {
+            Filter::Category(category) => {
+                let section = categories::display_name(categories::main_category(entry));
+                entry.categories().iter().map(String::as_str)
+                    .chain([section])
+                    .any(|name| name.to_lowercase().starts_with(category.as_str()))
+            }
+            Filter::Terminal(is_terminal) => entry.is_terminal() == *is_terminal,
+            Filter::Source(source) => Source::of(entry) == *source,
+            Filter::Kind(kind) => Kind::of(entry) == *kind,
+        }
+    }
+}
+
+/// A search box query: the text to match plus inline filters.
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+    pub matcher: MatcherKind,
+    /// The words left after removing filters, matched with `matcher`.
+    pub text: String,
+    /// Filters with whether they were negated with `-`.
+    pub filters: Vec<(Filter, bool)>,
+    /// Words from `-word` terms; entries containing any of them are left out.
+    pub excluded: Vec<String>,
+}
+
+impl Query {
+    pub fn accepts(&self, entry: &Entry) -> bool {
+        self.filters.iter().all(|(filter, negated)| filter.matches(entry) != *negated)
+    }
+}
+
+/// Parses the raw search box contents. Filters are `key:value` terms with one of the
+/// `FILTER_KEYS`, `-` in front of a filter negates it and in front of a word excludes entries
+/// containing it. A filter without a value is ignored so that nothing is reported while it is
+/// still being typed. Regular expression queries are taken verbatim.
+pub fn parse(raw: &str, default: MatcherKind) -> anyhow::Result<Query> {
+    let (kind, text) = matcher::split_query(raw, default);
+    let mut query = Query {
+        matcher: kind,
+        ..Default::default()
+    };
+    if kind == MatcherKind::Regex {
+        query.text = text.to_string();
+        return Ok(query);
+    }
+
+    let mut words = Vec::new();
+    for term in text.split_whitespace() {
+        let (negated, term) = match term.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, term),
+        };
+        match term.split_once(':') {
+            Some((key, value)) if FILTER_KEYS.contains(&key.to_lowercase().as_str()) => {
+                if !value.is_empty() {
+                    query.filters.push((Filter::parse(&key.to_lowercase(), value)?, negated));
+                }
+            }
+            _ if negated => {
+                if !term.is_empty() {
+                    query.excluded.push(term.to_string());
+                }
+            }
+            _ => words.push(term),
+        }
+    }
+    query.text = words.join(" ");
+    Ok(query)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_skim(raw: &str) -> Query {
+        parse(raw, MatcherKind::Skim).unwrap()
+    }
+
+    fn parse_error(raw: &str) -> String {
+        parse(raw, MatcherKind::Skim).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn filters_are_taken_out_of_the_text() {
+        let query = parse_skim("cat:Dev text term:no  SRC:flatpak kind:app editor");
+        assert_eq!(query.text, "text editor");
+        assert_eq!(query.filters, vec![
+            (Filter::Category("dev".to_string()), false),
+            (Filter::Terminal(false), false),
+            (Filter::Source(Source::Flatpak), false),
+            (Filter::Kind(Kind::Application), false),
+        ]);
+        assert!(query.excluded.is_empty());
+    }
+
+    #[test]
+    fn filters_without_a_value_are_ignored() {
+        let query = parse_skim("editor cat:");
+        assert_eq!(query.text, "editor");
+        assert!(query.filters.is_empty());
+    }
+
+    #[test]
+    fn other_words_with_colons_are_searched_for() {
+        assert_eq!(parse_skim("firefox: new").text, "firefox: new");
+        assert_eq!(parse_skim("firefox:new").text, "firefox:new");
+        assert_eq!(parse_skim("https://example.org").text, "https://example.org");
+        assert!(parse_skim("firefox:new").filters.is_empty());
+    }
+
+    #[test]
+    fn minus_negates_filters_and_excludes_words() {
+        let query = parse_skim("-cat:game -term:yes player -beta -");
+        assert_eq!(query.text, "player");
+        assert_eq!(query.filters, vec![
+            (Filter::Category("game".to_string()), true),
+            (Filter::Terminal(true), true),
+        ]);
+        assert_eq!(query.excluded, vec!["beta".to_string()]);
+    }
+
+    #[test]
+    fn regular_expressions_are_taken_verbatim() {
+        let query = parse_skim("/cat:.* -beta$");
+        assert_eq!(query.matcher, MatcherKind::Regex);
+        assert_eq!(query.text, "cat:.* -beta$");
+        assert!(query.filters.is_empty());
+        assert!(query.excluded.is_empty());
+
+        let query = parse("^fire cat:net", MatcherKind::Skim).unwrap();
+        assert_eq!(query.matcher, MatcherKind::Prefix);
+        assert_eq!(query.text, "fire");
+    }
+
+    #[test]
+    fn invalid_filter_values_are_reported() {
+        assert_eq!(parse_error("term:maybe"), "Expected yes or no after term:, found \"maybe\"");
+        assert_eq!(
+            parse_error("src:apt"),
+            "Unknown source \"apt\"; expected flatpak, snap, nix, user, system or builtin",
+        );
+        assert_eq!(parse_error("kind:Widget"), "Unknown kind \"widget\"; expected app, action or link");
+    }
+
+    #[test]
+    fn accepts_applies_negated_filters() {
+        let entry = Entry::builder()
+            .id("org.example.Editor.desktop")
+            .title("Editor")
+            .categories(vec!["Development".to_string(), "TextEditor".to_string()])
+            .is_terminal(false)
+            .build();
+        assert!(parse_skim("cat:dev -term:yes").accepts(&entry));
+        assert!(parse_skim("cat:texted").accepts(&entry));
+        assert!(!parse_skim("-cat:dev").accepts(&entry));
+        assert!(!parse_skim("kind:link").accepts(&entry));
+    }
+}
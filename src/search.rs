@@ -0,0 +1,167 @@
+use std::ops::Range;
+
+use gpui::SharedString;
+
+use crate::config::SearchWeights;
+use crate::fold::{Folded, FoldedEntry, FoldingMatcher};
+
+fn best_match<'a>(matcher: &FoldingMatcher, values: impl IntoIterator<Item = &'a Folded>) -> Option<i64> {
+    values.into_iter()
+        .filter_map(|value| matcher.find(value))
+        .map(|found| found.score)
+        .max()
+}
+
+fn weighted(score: Option<i64>, weight: f64) -> Option<i64> {
+    score.map(|score| (score as f64 * weight).round() as i64)
+}
+
+/// Scores an entry against one query token as the best weighted match over its searchable fields.
+fn score_token(matcher: &FoldingMatcher, entry: &FoldedEntry, weights: &SearchWeights) -> Option<i64> {
+    let title_score = best_match(matcher, std::iter::once(&entry.title).chain(&entry.untranslated_title));
+    let generic_name_score = best_match(matcher, &entry.generic_name);
+    let keywords_score = best_match(matcher, &entry.keywords);
+    let categories_score = best_match(matcher, &entry.categories);
+    let executable_score = best_match(matcher, &entry.executable);
+
+    [
+        weighted(title_score, weights.title),
+        weighted(generic_name_score, weights.generic_name),
+        weighted(keywords_score, weights.keywords),
+        weighted(categories_score, weights.categories),
+        weighted(executable_score, weights.executable),
+    ]
+        .into_iter()
+        .flatten()
+        .max()
+}
+
+/// Scores an entry against a query with one matcher per token. Every token has to match some
+/// field, in any order, and the entry scores the sum of its token scores.
+pub fn score_entry(matchers: &[FoldingMatcher], entry: &FoldedEntry, weights: &SearchWeights) -> Option<i64> {
+    matchers.iter()
+        .map(|matcher| score_token(matcher, entry, weights))
+        .sum()
+}
+
+/// Matched character indices to emphasize when rendering a result.
+#[derive(Debug, Clone, Default)]
+pub struct Highlights {
+    pub title: Vec<usize>,
+    /// Shown in place of the description when some query token matched the generic name or a
+    /// keyword but not the title, so that the row shows why the entry was found.
+    pub detail: Option<Detail>,
+}
+
+/// A searched field other than the title, with the characters the query matched in it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Detail {
+    pub text: SharedString,
+    pub indices: Vec<usize>,
+}
+
+fn matched_indices(matchers: &[&FoldingMatcher], value: &Folded) -> Vec<usize> {
+    let mut indices: Vec<usize> = matchers.iter()
+        .filter_map(|matcher| matcher.find(value))
+        .flat_map(|found| found.indices)
+        .collect();
+    indices.sort_unstable();
+    indices.dedup();
+    indices
+}
+
+/// Collects the characters of the title matched by any of the query tokens. Tokens that do not
+/// match the title are looked up in the generic name and then the keywords, and the first of
+/// those fields they match becomes the detail.
+pub fn highlights(matchers: &[FoldingMatcher], entry: &FoldedEntry) -> Highlights {
+    let (in_title, elsewhere): (Vec<&FoldingMatcher>, Vec<&FoldingMatcher>) = matchers.iter()
+        .partition(|matcher| matcher.find(&entry.title).is_some());
+
+    let detail = if elsewhere.is_empty() {
+        None
+    } else {
+        entry.generic_name.iter().chain(&entry.keywords)
+            .map(|field| Detail {
+                text: SharedString::from(field.original.clone()),
+                indices: matched_indices(&elsewhere, field),
+            })
+            .find(|detail| !detail.indices.is_empty())
+    };
+
+    Highlights {
+        title: matched_indices(&in_title, &entry.title),
+        detail,
+    }
+}
+
+/// Converts matched character indices into byte ranges of `text`, merging adjacent characters.
+pub fn highlight_ranges(text: &str, indices: &[usize]) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    for (char_index, (start, c)) in text.char_indices().enumerate() {
+        if !indices.contains(&char_index) {
+            continue;
+        }
+        let end = start + c.len_utf8();
+        match ranges.last_mut() {
+            Some(range) if range.end == start => range.end = end,
+            _ => ranges.push(start..end),
+        }
+    }
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entry::Entry;
+    use crate::fold::Folder;
+    use crate::matcher::{MatcherKind, new_matcher};
+
+    fn matchers(kind: MatcherKind, query: &str) -> Vec<FoldingMatcher> {
+        query.split_whitespace()
+            .map(|token| FoldingMatcher { inner: new_matcher(kind, token).unwrap(), folded: true })
+            .collect()
+    }
+
+    fn editor() -> FoldedEntry {
+        Folder::default().fold_entry(&Entry::builder()
+            .id("org.example.Editor.desktop")
+            .title("Mousepad")
+            .generic_name(Some("Text Editor"))
+            .description(Some("Simple text editor"))
+            .keywords(vec!["notepad".to_string(), "plain".to_string()])
+            .build())
+    }
+
+    #[test]
+    fn ranges_are_byte_ranges_of_characters() {
+        assert_eq!(highlight_ranges("Überprüfung", &[0, 6]), vec![0..2, 7..9]);
+        assert_eq!(highlight_ranges("日本語", &[1]), vec![3..6]);
+        assert!(highlight_ranges("Firefox", &[]).is_empty());
+        assert!(highlight_ranges("Firefox", &[42]).is_empty());
+    }
+
+    #[test]
+    fn adjacent_characters_merge_into_one_range() {
+        assert_eq!(highlight_ranges("Firefox", &[0, 1, 2, 4, 5]), vec![0..3, 4..6]);
+        assert_eq!(highlight_ranges("prüfen", &[1, 2, 3]), vec![1..5]);
+    }
+
+    #[test]
+    fn title_matches_are_highlighted_in_the_title() {
+        let found = highlights(&matchers(MatcherKind::Prefix, "mouse"), &editor());
+        assert_eq!(found.title, vec![0, 1, 2, 3, 4]);
+        assert_eq!(found.detail, None);
+    }
+
+    #[test]
+    fn other_matches_are_shown_as_the_detail() {
+        let found = highlights(&matchers(MatcherKind::Substring, "pad edit"), &editor());
+        assert_eq!(found.title, vec![5, 6, 7]);
+        assert_eq!(found.detail, Some(Detail { text: "Text Editor".into(), indices: vec![5, 6, 7, 8] }));
+
+        let found = highlights(&matchers(MatcherKind::Prefix, "note"), &editor());
+        assert!(found.title.is_empty());
+        assert_eq!(found.detail, Some(Detail { text: "notepad".into(), indices: vec![0, 1, 2, 3] }));
+    }
+}
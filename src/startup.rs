@@ -0,0 +1,283 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use x11rb::connection::Connection;
+use x11rb::protocol::Event;
+use x11rb::protocol::xproto::{
+    AtomEnum, ClientMessageEvent, ConnectionExt, CreateWindowAux, EventMask, PropMode, Window, WindowClass,
+};
+use x11rb::rust_connection::RustConnection;
+use x11rb::wrapper::ConnectionExt as _;
+use x11rb::COPY_DEPTH_FROM_PARENT;
+use wayland_client::globals::{GlobalListContents, registry_queue_init};
+use wayland_client::protocol::wl_registry::{self, WlRegistry};
+use wayland_client::{Connection as WaylandConnection, Dispatch, QueueHandle};
+use wayland_protocols::xdg::activation::v1::client::xdg_activation_token_v1::{self, XdgActivationTokenV1};
+use wayland_protocols::xdg::activation::v1::client::xdg_activation_v1::{self, XdgActivationV1};
+
+use crate::entry::Entry;
+
+static SEQUENCE: AtomicUsize = AtomicUsize::new(0);
+
+/// The startup notification handed to a launched application.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StartupToken {
+    /// An `xdg_activation_v1` token, exported as `XDG_ACTIVATION_TOKEN`.
+    Wayland(String),
+    /// A startup notification ID announced on the X11 root window, exported as `DESKTOP_STARTUP_ID`.
+    X11(String),
+}
+
+impl StartupToken {
+    pub fn id(&self) -> &str {
+        match self {
+            StartupToken::Wayland(token) => token,
+            StartupToken::X11(id) => id,
+        }
+    }
+
+    /// Environment variables that carry the token to the launched process.
+    pub fn env(&self) -> Vec<(&'static str, &str)> {
+        match self {
+            StartupToken::Wayland(token) => vec![("XDG_ACTIVATION_TOKEN", token), ("DESKTOP_STARTUP_ID", token)],
+            StartupToken::X11(id) => vec![("DESKTOP_STARTUP_ID", id)],
+        }
+    }
+}
+
+/// Variables that must not leak from the launcher's own environment into launched processes.
+pub const STARTUP_ENV_VARS: &[&str] = &["XDG_ACTIVATION_TOKEN", "DESKTOP_STARTUP_ID"];
+
+/// Starts startup notification for an entry with `StartupNotify=true`. Call it once per launch
+/// and hand the token to every attempt, then `cancel` it if none of them succeeds.
+///
+/// On Wayland a fresh `xdg_activation_v1` token is requested. gpui does not expose its own
+/// connection, so the request goes over a separate one without a surface; compositors that
+/// require the focused surface may hand out a token that does not grant focus. On X11 a new
+/// startup sequence is announced on the root window. When neither works the application
+/// starts without startup notification.
+pub fn begin(entry: &Entry) -> Option<StartupToken> {
+    if !entry.startup_notify() {
+        return None;
+    }
+
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        match request_activation_token(entry) {
+            Ok(token) => return Some(StartupToken::Wayland(token)),
+            Err(err) => log::warn!("Failed to request an activation token for {:?}: {}", entry.id(), err),
+        }
+        return None;
+    }
+
+    if std::env::var_os("DISPLAY").is_some() {
+        match announce(entry) {
+            Ok(id) => return Some(StartupToken::X11(id)),
+            Err(err) => log::warn!("Failed to send startup notification for {:?}: {}", entry.id(), err),
+        }
+    }
+
+    None
+}
+
+/// Ends a startup sequence whose application could not be started, so that the busy cursor
+/// and any task bar placeholder go away.
+pub fn cancel(token: &StartupToken) {
+    // Unused activation tokens simply expire.
+    if let StartupToken::Wayland(_) = token {
+        return;
+    }
+    let result = Messenger::connect().and_then(|messenger| messenger.send(&format!("remove: ID={}", quote(token.id()))));
+    if let Err(err) = result {
+        log::warn!("Failed to end startup notification {:?}: {}", token.id(), err);
+    }
+}
+
+#[derive(Default)]
+struct ActivationState {
+    token: Option<String>,
+}
+
+impl Dispatch<WlRegistry, GlobalListContents> for ActivationState {
+    fn event(_: &mut Self, _: &WlRegistry, _: wl_registry::Event, _: &GlobalListContents, _: &WaylandConnection, _: &QueueHandle<Self>) {}
+}
+
+impl Dispatch<XdgActivationV1, ()> for ActivationState {
+    fn event(_: &mut Self, _: &XdgActivationV1, _: xdg_activation_v1::Event, _: &(), _: &WaylandConnection, _: &QueueHandle<Self>) {}
+}
+
+impl Dispatch<XdgActivationTokenV1, ()> for ActivationState {
+    fn event(state: &mut Self, _: &XdgActivationTokenV1, event: xdg_activation_token_v1::Event, _: &(), _: &WaylandConnection, _: &QueueHandle<Self>) {
+        if let xdg_activation_token_v1::Event::Done { token } = event {
+            state.token = Some(token);
+        }
+    }
+}
+
+/// Requests an activation token for the application of `entry` from the compositor.
+fn request_activation_token(entry: &Entry) -> anyhow::Result<String> {
+    let conn = WaylandConnection::connect_to_env()?;
+    let (globals, mut queue) = registry_queue_init::<ActivationState>(&conn)?;
+    let qh = queue.handle();
+    let activation: XdgActivationV1 = globals.bind(&qh, 1..=1, ())?;
+
+    let token = activation.get_activation_token(&qh, ());
+    let desktop_id = entry.parent_id().unwrap_or(entry.id());
+    if let Some(app_id) = desktop_id.strip_suffix(".desktop") {
+        token.set_app_id(app_id.to_string());
+    }
+    token.commit();
+
+    let mut state = ActivationState::default();
+    let result = loop {
+        if let Some(token) = state.token.take() {
+            break Ok(token);
+        }
+        if let Err(err) = queue.blocking_dispatch(&mut state) {
+            break Err(err.into());
+        }
+    };
+    token.destroy();
+    activation.destroy();
+    let _ = conn.flush();
+    result
+}
+
+/// Announces a new startup sequence and returns its ID.
+fn announce(entry: &Entry) -> anyhow::Result<String> {
+    let messenger = Messenger::connect()?;
+    let time = messenger.server_time()
+        .inspect_err(|err| log::debug!("Cannot determine the X server time: {}", err))
+        .ok();
+    let id = startup_id(entry, time);
+    messenger.send(&new_message(entry, &id, messenger.screen_num))?;
+    Ok(id)
+}
+
+/// Builds a unique startup ID. `time` is the X server time of the launch, which window managers
+/// compare with `_NET_WM_USER_TIME` for focus stealing prevention; the `_TIME` part is left out
+/// when it is unknown.
+fn startup_id(entry: &Entry, time: Option<u32>) -> String {
+    let hostname = std::fs::read_to_string("/proc/sys/kernel/hostname").unwrap_or_default();
+    let program = entry.executable().unwrap_or("unknown");
+    let sequence = SEQUENCE.fetch_add(1, Ordering::Relaxed);
+    let mut id = format!("yde-launcher-{}-{}-{}-{}", std::process::id(), hostname.trim(), program, sequence);
+    if let Some(time) = time {
+        id.push_str(&format!("_TIME{time}"));
+    }
+    id
+}
+
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn new_message(entry: &Entry, id: &str, screen: usize) -> String {
+    let mut message = format!("new: ID={} NAME={} SCREEN={}", quote(id), quote(&entry.title()), screen);
+    if let Some(program) = entry.executable() {
+        message.push_str(&format!(" BIN={}", quote(program)));
+    }
+    if let Some(icon) = entry.icon() {
+        message.push_str(&format!(" ICON={}", quote(icon)));
+    }
+    if let Some(wm_class) = entry.startup_wm_class() {
+        message.push_str(&format!(" WMCLASS={}", quote(wm_class)));
+    }
+    message
+}
+
+/// An X11 connection with the helper window that startup notification messages are sent from.
+struct Messenger {
+    conn: RustConnection,
+    screen_num: usize,
+    root: Window,
+    window: Window,
+}
+
+impl Messenger {
+    fn connect() -> anyhow::Result<Self> {
+        let (conn, screen_num) = x11rb::connect(None)?;
+        let root = conn.setup().roots[screen_num].root;
+        let window = conn.generate_id()?;
+        conn.create_window(
+            COPY_DEPTH_FROM_PARENT,
+            window,
+            root,
+            -100,
+            -100,
+            1,
+            1,
+            0,
+            WindowClass::INPUT_ONLY,
+            0,
+            &CreateWindowAux::new().override_redirect(1).event_mask(EventMask::PROPERTY_CHANGE),
+        )?;
+        Ok(Messenger { conn, screen_num, root, window })
+    }
+
+    /// Reads the current server time from the `PropertyNotify` event of a zero-length append
+    /// to a property of the helper window.
+    fn server_time(&self) -> anyhow::Result<u32> {
+        let atom = self.conn.intern_atom(false, b"_NET_STARTUP_INFO")?.reply()?.atom;
+        self.conn.change_property8(PropMode::APPEND, self.window, atom, AtomEnum::STRING, &[])?;
+        self.conn.flush()?;
+        loop {
+            if let Event::PropertyNotify(event) = self.conn.wait_for_event()?
+                && event.window == self.window
+            {
+                return Ok(event.time);
+            }
+        }
+    }
+
+    /// Sends a startup notification message to the root window as a series of client messages.
+    fn send(&self, message: &str) -> anyhow::Result<()> {
+        let begin_atom = self.conn.intern_atom(false, b"_NET_STARTUP_INFO_BEGIN")?.reply()?.atom;
+        let info_atom = self.conn.intern_atom(false, b"_NET_STARTUP_INFO")?.reply()?.atom;
+
+        let mut bytes = message.as_bytes().to_vec();
+        bytes.push(0);
+        for (index, chunk) in bytes.chunks(20).enumerate() {
+            let mut data = [0u8; 20];
+            data[..chunk.len()].copy_from_slice(chunk);
+            let atom = if index == 0 { begin_atom } else { info_atom };
+            let event = ClientMessageEvent::new(8, self.window, atom, data);
+            self.conn.send_event(false, self.root, EventMask::PROPERTY_CHANGE, event)?;
+        }
+        self.conn.flush()?;
+        Ok(())
+    }
+}
+
+impl Drop for Messenger {
+    fn drop(&mut self) {
+        let _ = self.conn.destroy_window(self.window);
+        let _ = self.conn.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn editor() -> Entry {
+        Entry::builder()
+            .id("org.example.Editor.desktop")
+            .title("Text \"Editor\"")
+            .executable(Some("editor"))
+            .icon(Some("accessories-text-editor"))
+            .build()
+    }
+
+    #[test]
+    fn startup_id_carries_the_server_time() {
+        assert!(startup_id(&editor(), Some(4242)).ends_with("_TIME4242"));
+        assert!(!startup_id(&editor(), None).contains("_TIME"));
+    }
+
+    #[test]
+    fn new_message_uses_the_screen_and_quotes_values() {
+        assert_eq!(
+            new_message(&editor(), "id-1", 1),
+            r#"new: ID="id-1" NAME="Text \"Editor\"" SCREEN=1 BIN="editor" ICON="accessories-text-editor""#,
+        );
+    }
+}
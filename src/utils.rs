@@ -1,3 +1,5 @@
+use std::path::{Path, PathBuf};
+
 use gpui::{Hsla, Rgba};
 
 pub trait WithAlpha {
@@ -26,3 +28,56 @@ impl WithAlpha for Rgba {
         }
     }
 }
+
+/// Resolves an XDG base directory variable such as `XDG_CACHE_HOME`. Unset, empty and relative
+/// values fall back to `default` below the home directory.
+pub fn xdg_home(var: &str, default: &str) -> Option<PathBuf> {
+    std::env::var(var).ok()
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| std::env::home_dir().map(|home| home.join(default)))
+}
+
+/// Writes a file through a temporary file and a rename, so that a concurrent reader never sees
+/// it half written. Missing parent directories are created.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    std::fs::write(&tmp_path, contents)?;
+    std::fs::rename(&tmp_path, path)
+}
+
+fn is_executable_file(path: &Path) -> bool {
+    #[cfg(target_family = "unix")]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        path.metadata()
+            .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+            .unwrap_or(false)
+    }
+    #[cfg(not(target_family = "unix"))]
+    {
+        path.is_file()
+    }
+}
+
+/// Resolves a program name the way `execvp` would: paths containing a slash are used as is,
+/// bare names are looked up in `$PATH`. Only files with an executable bit are accepted.
+pub fn find_executable(program: &str) -> Option<PathBuf> {
+    if program.is_empty() {
+        return None;
+    }
+    if program.contains('/') {
+        let path = PathBuf::from(program);
+        return is_executable_file(&path).then_some(path);
+    }
+
+    let path_var = std::env::var_os("PATH")?;
+    std::env::split_paths(&path_var)
+        .map(|dir| dir.join(program))
+        .find(|path| is_executable_file(path))
+}
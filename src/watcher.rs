@@ -0,0 +1,300 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use futures::channel::mpsc::{UnboundedReceiver, UnboundedSender, unbounded};
+use notify_debouncer_mini::notify::{RecursiveMode, Watcher};
+use notify_debouncer_mini::{DebounceEventResult, new_debouncer};
+
+use crate::actions;
+use crate::cache::{self, CacheKey};
+use crate::config::Config;
+use crate::data::{self, DesktopFile};
+use crate::entry::Entry;
+use crate::icons;
+
+const DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// A change to apply to the launcher state.
+#[derive(Debug, Clone)]
+pub enum Update {
+    /// Replaces every entry provided by the desktop file `id` (the entry and its actions).
+    Entries { id: String, entries: Vec<Entry> },
+    /// Newly resolved icon paths, merged into the icon map.
+    Icons(HashMap<String, PathBuf>),
+    /// Replaces all desktop entries and icons, sent when the startup cache turned out stale.
+    Reload { entries: Vec<Entry>, icon_map: HashMap<String, PathBuf> },
+}
+
+impl Update {
+    /// Applies the update to a list of desktop entries and an icon map.
+    pub fn apply_to(self, entries: &mut Vec<Entry>, icon_map: &mut HashMap<String, PathBuf>) {
+        match self {
+            Update::Entries { id, entries: new_entries } => {
+                entries.retain(|entry| entry.id() != id && entry.parent_id() != Some(id.as_str()));
+                entries.extend(new_entries);
+            }
+            Update::Icons(icons) => icon_map.extend(icons),
+            Update::Reload { entries: new_entries, icon_map: new_icon_map } => {
+                *entries = new_entries;
+                *icon_map = new_icon_map;
+            }
+        }
+    }
+}
+
+fn get_winning_files() -> HashMap<String, DesktopFile> {
+    data::get_desktop_entry_paths().into_iter()
+        .map(|file| (file.id.clone(), file))
+        .collect()
+}
+
+struct WatchState {
+    config: Config,
+    desktop_files: HashMap<String, DesktopFile>,
+    known_icons: HashSet<String>,
+    icon_dirs: Vec<PathBuf>,
+}
+
+impl WatchState {
+    /// Turns a batch of changed paths into updates. The directory listing is cheap to redo,
+    /// so it is rescanned in full and only the desktop files whose ID is affected are parsed.
+    fn handle_changes(&mut self, paths: &[PathBuf]) -> Vec<Update> {
+        let mut updates = Vec::new();
+
+        let desktop_files = get_winning_files();
+        let changed = |file: &DesktopFile| paths.iter().any(|path| file.path.starts_with(path));
+        let affected_ids: HashSet<&String> = desktop_files.iter()
+            .filter(|(id, file)| self.desktop_files.get(*id) != Some(file) || changed(file))
+            .map(|(id, _)| id)
+            .chain(self.desktop_files.keys().filter(|id| !desktop_files.contains_key(*id)))
+            .collect();
+
+        let desktops = self.config.current_desktops();
+        let mut new_icons = HashSet::new();
+        for id in affected_ids {
+            let entries = desktop_files.get(id)
+                .and_then(|file| data::evaluate_desktop_file(file, &self.config, &desktops).ok())
+                .unwrap_or_default();
+            for icon in entries.iter().filter_map(|entry| entry.icon()) {
+                if self.known_icons.insert(icon.to_string()) {
+                    new_icons.insert(icon.to_string());
+                }
+            }
+            updates.push(Update::Entries { id: id.clone(), entries });
+        }
+        self.desktop_files = desktop_files;
+
+        let icons_changed = paths.iter().any(|path| self.icon_dirs.iter().any(|dir| path.starts_with(dir)));
+        let icons_to_resolve = if icons_changed { &self.known_icons } else { &new_icons };
+        if !icons_to_resolve.is_empty() {
+            updates.push(Update::Icons(icons::find_icons(icons_to_resolve.iter().map(|icon| icon.as_str()))));
+        }
+
+        updates
+    }
+}
+
+/// The nearest ancestor of `dir` that exists, which is watched until `dir` is created.
+fn nearest_existing_ancestor(dir: &Path) -> Option<&Path> {
+    dir.ancestors().skip(1).find(|ancestor| ancestor.is_dir())
+}
+
+/// Watches a set of directories recursively, including ones that do not exist yet, such as
+/// `~/.local/share/applications` on a fresh account. In place of a missing directory its
+/// nearest existing ancestor is watched non-recursively, and the directory is picked up on
+/// the next batch of changes once it has been created.
+struct DirWatches {
+    dirs: Vec<PathBuf>,
+    missing: Vec<PathBuf>,
+    ancestors: HashSet<PathBuf>,
+}
+
+impl DirWatches {
+    fn new(watcher: &mut dyn Watcher, dirs: Vec<PathBuf>) -> Self {
+        let mut watches = DirWatches { missing: dirs.clone(), dirs, ancestors: HashSet::new() };
+        watches.refresh(watcher);
+        watches
+    }
+
+    /// Starts watching the missing directories that have been created since the last call and
+    /// moves deleted ones back to the missing list. Returns the newly watched directories.
+    fn refresh(&mut self, watcher: &mut dyn Watcher) -> Vec<PathBuf> {
+        for dir in &self.dirs {
+            if !dir.is_dir() && !self.missing.contains(dir) {
+                let _ = watcher.unwatch(dir);
+                self.missing.push(dir.clone());
+            }
+        }
+
+        let mut created = Vec::new();
+        // Repeat until nothing changes, a directory may be created right before the watch on
+        // its ancestor is in place.
+        loop {
+            self.missing.retain(|dir| {
+                if !dir.is_dir() {
+                    return true;
+                }
+                if let Err(err) = watcher.watch(dir, RecursiveMode::Recursive) {
+                    log::warn!("Failed to watch {:?}: {}", dir, err);
+                }
+                created.push(dir.clone());
+                false
+            });
+
+            let ancestors: HashSet<PathBuf> = self.missing.iter()
+                .filter_map(|dir| nearest_existing_ancestor(dir))
+                // A recursive watch already covers the ancestor.
+                .filter(|ancestor| !self.dirs.iter().any(|dir| !self.missing.contains(dir) && ancestor.starts_with(dir)))
+                .map(Path::to_path_buf)
+                .collect();
+            for ancestor in self.ancestors.difference(&ancestors) {
+                let _ = watcher.unwatch(ancestor);
+            }
+            for ancestor in ancestors.difference(&self.ancestors) {
+                if let Err(err) = watcher.watch(ancestor, RecursiveMode::NonRecursive) {
+                    log::warn!("Failed to watch {:?}: {}", ancestor, err);
+                }
+            }
+            self.ancestors = ancestors;
+            if !self.missing.iter().any(|dir| dir.is_dir()) {
+                return created;
+            }
+        }
+    }
+
+    /// Whether a changed path is inside a watched directory or on the way to a missing one,
+    /// as opposed to an unrelated file next to it in a watched ancestor.
+    fn is_relevant(&self, path: &Path) -> bool {
+        self.dirs.iter().any(|dir| path.starts_with(dir) || dir.starts_with(path))
+    }
+}
+
+/// Watches the `applications` and icon directories on a background thread and sends the
+/// resulting updates in debounced batches. When the entries came from the cache, `cache_key`
+/// is validated first and a full reload is sent if it no longer matches. The cache is
+/// rewritten after every batch, so that the next start does not have to rescan.
+pub fn spawn(
+    config: Config,
+    entries: Vec<Entry>,
+    icon_map: HashMap<String, PathBuf>,
+    cache_key: Option<CacheKey>,
+) -> UnboundedReceiver<Vec<Update>> {
+    let (sender, receiver): (UnboundedSender<Vec<Update>>, _) = unbounded();
+
+    std::thread::spawn(move || {
+        let (mut entries, mut icon_map) = (entries, icon_map);
+        let mut known_icons: HashSet<String> = entries.iter()
+            .filter_map(|entry| entry.icon())
+            .map(str::to_string)
+            .chain(icon_map.keys().cloned())
+            .collect();
+        if let Some(cache_key) = cache_key {
+            let current_key = cache::current_key(&config);
+            if current_key != cache_key {
+                entries = data::get_desktop_entries(&config);
+                let builtin_actions = actions::get_builtin_actions();
+                let icon_names: HashSet<&str> = entries.iter().chain(&builtin_actions)
+                    .filter_map(|entry| entry.icon())
+                    .collect();
+                icon_map = icons::find_icons(icon_names);
+                known_icons = icon_map.keys().cloned().collect();
+                cache::store(current_key, &entries, &icon_map);
+                let reload = Update::Reload { entries: entries.clone(), icon_map: icon_map.clone() };
+                if sender.unbounded_send(vec![reload]).is_err() {
+                    return;
+                }
+            }
+        }
+
+        let data_dirs = data::get_data_dirs();
+        let mut state = WatchState {
+            config,
+            desktop_files: get_winning_files(),
+            known_icons,
+            icon_dirs: icons::get_icon_dirs(&data_dirs),
+        };
+
+        let (event_sender, event_receiver) = std::sync::mpsc::channel::<DebounceEventResult>();
+        let mut debouncer = match new_debouncer(DEBOUNCE_TIMEOUT, event_sender) {
+            Ok(debouncer) => debouncer,
+            Err(err) => {
+                log::error!("Failed to start the file watcher: {}", err);
+                return;
+            }
+        };
+        let dirs = data_dirs.iter().map(|dir| dir.join("applications")).chain(state.icon_dirs.iter().cloned()).collect();
+        let mut watches = DirWatches::new(debouncer.watcher(), dirs);
+
+        for result in event_receiver {
+            let events = match result {
+                Ok(events) => events,
+                Err(err) => {
+                    log::warn!("File watcher error: {}", err);
+                    continue;
+                }
+            };
+            let mut paths: Vec<PathBuf> = events.into_iter()
+                .map(|event| event.path)
+                .filter(|path| watches.is_relevant(path))
+                .collect();
+            paths.extend(watches.refresh(debouncer.watcher()));
+            if paths.is_empty() {
+                continue;
+            }
+            // The key is taken before scanning so that changes made during the scan invalidate it.
+            let cache_key = cache::current_key(&state.config);
+            let updates = state.handle_changes(&paths);
+            if updates.is_empty() {
+                continue;
+            }
+            for update in updates.iter().cloned() {
+                update.apply_to(&mut entries, &mut icon_map);
+            }
+            cache::store(cache_key, &entries, &icon_map);
+            if sender.unbounded_send(updates).is_err() {
+                break;
+            }
+        }
+    });
+
+    receiver
+}
+
+#[cfg(test)]
+mod tests {
+    use notify_debouncer_mini::notify::NullWatcher;
+
+    use super::*;
+
+    #[test]
+    fn missing_directories_are_picked_up_once_created() {
+        let root = std::env::temp_dir().join(format!("yde-launcher-watcher-{}", std::process::id()));
+        let icons = root.join("icons");
+        let applications = root.join("share/applications");
+        std::fs::create_dir_all(&icons).unwrap();
+
+        let mut watcher = NullWatcher;
+        let mut watches = DirWatches::new(&mut watcher, vec![applications.clone(), icons.clone()]);
+        assert_eq!(watches.missing, std::slice::from_ref(&applications));
+        assert_eq!(watches.ancestors, HashSet::from([root.clone()]));
+        assert!(watches.is_relevant(&root.join("share")));
+        assert!(watches.is_relevant(&icons.join("hicolor")));
+        assert!(!watches.is_relevant(&root.join("recently-used.xbel")));
+
+        std::fs::create_dir(root.join("share")).unwrap();
+        assert!(watches.refresh(&mut watcher).is_empty());
+        assert_eq!(watches.ancestors, HashSet::from([root.join("share")]));
+
+        std::fs::create_dir(&applications).unwrap();
+        assert_eq!(watches.refresh(&mut watcher), std::slice::from_ref(&applications));
+        assert!(watches.missing.is_empty());
+        assert!(watches.ancestors.is_empty());
+
+        std::fs::remove_dir(&applications).unwrap();
+        assert!(watches.refresh(&mut watcher).is_empty());
+        assert_eq!(watches.missing, [applications]);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}